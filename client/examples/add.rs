@@ -1,6 +1,6 @@
 use arithmetic::add::Add;
 use client::{
-    initialize_client, interact_with_program_instructions, send_and_confirm_transactions,
+    execute_steps, initialize_client, initialize_instruction, interact_with_program_instructions,
     setup_payer, setup_program, ClientError, Config,
 };
 use solana_sdk::{
@@ -12,7 +12,10 @@ use solana_system_interface::instruction::create_account;
 use stark::swiftness::stark::types::cast_struct_to_slice;
 use std::{mem::size_of, path::Path};
 use utils::{AccountCast, BidirectionalStack, Executable};
-use verifier::{instruction::VerifierInstruction, state::BidirectionalStackAccount};
+use verifier::{
+    instruction::VerifierInstruction,
+    state::{BidirectionalStackAccount, DEFAULT_CAPACITY, DEFAULT_LENGTH_SIZE},
+};
 
 /// Main entry point for the Solana program client
 #[tokio::main]
@@ -54,7 +57,10 @@ async fn main() -> client::Result<()> {
 
     // Create and send the transaction
     let create_account_tx = Transaction::new_signed_with_payer(
-        &[create_account_ix],
+        &[
+            create_account_ix,
+            initialize_instruction(&program_id, &stack_account.pubkey(), &payer.pubkey()),
+        ],
         Some(&payer.pubkey()),
         &[&payer, &stack_account],
         client.get_latest_blockhash().await?,
@@ -65,13 +71,19 @@ async fn main() -> client::Result<()> {
         .await?;
     println!("Account created successfully: {signature}");
 
-    let mut stack_init_input: [u64; 2] = [0, 65536];
+    let mut stack_init_input: [u64; 3] = [0, 65536, 0];
     let stack_init_bytes = cast_struct_to_slice(&mut stack_init_input);
     // Initialize the account
     let init_ix = Instruction::new_with_borsh(
         program_id,
-        &VerifierInstruction::SetAccountData(0, stack_init_bytes.to_vec()),
-        vec![AccountMeta::new(stack_account.pubkey(), false)],
+        &VerifierInstruction::SetAccountData(
+            std::mem::offset_of!(BidirectionalStackAccount, front_index),
+            stack_init_bytes.to_vec(),
+        ),
+        vec![
+            AccountMeta::new(stack_account.pubkey(), false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
+        ],
     );
 
     let signature = interact_with_program_instructions(
@@ -103,7 +115,10 @@ async fn main() -> client::Result<()> {
     let push_task_ix = Instruction::new_with_borsh(
         program_id,
         &VerifierInstruction::PushTask(Add::new(48, 52).to_vec_with_type_tag()),
-        vec![AccountMeta::new(stack_account.pubkey(), false)],
+        vec![
+            AccountMeta::new(stack_account.pubkey(), false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
+        ],
     );
 
     let signature = interact_with_program_instructions(
@@ -128,25 +143,20 @@ async fn main() -> client::Result<()> {
     let simulation_steps = stack_after_push.simulate();
     println!("Steps in simulation: {simulation_steps}");
 
-    let mut transactions = Vec::new();
-    for i in 0..simulation_steps {
-        // Execute the task
-        let execute_ix = Instruction::new_with_borsh(
-            program_id,
-            &VerifierInstruction::Execute(i as u32),
-            vec![AccountMeta::new(stack_account.pubkey(), false)],
-        );
-
-        let execute_tx = Transaction::new_signed_with_payer(
-            &[execute_ix],
-            Some(&payer.pubkey()),
-            &[&payer],
-            client.get_latest_blockhash().await?,
-        );
-        transactions.push(execute_tx.clone());
-    }
-
-    send_and_confirm_transactions(&client, &transactions).await?;
+    let account_data = client
+        .get_account_data(&stack_account.pubkey())
+        .await
+        .map_err(ClientError::SolanaClientError)?;
+    execute_steps::<DEFAULT_CAPACITY, DEFAULT_LENGTH_SIZE>(
+        &client,
+        &payer,
+        &program_id,
+        &stack_account.pubkey(),
+        None,
+        account_data,
+        &config,
+    )
+    .await?;
 
     // Read and display the result
     let account_data = client
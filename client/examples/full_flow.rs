@@ -1,11 +1,11 @@
 use std::path::Path;
 
 use client::{
-    initialize_client, interact_with_program_instructions, send_and_confirm_transactions,
-    setup_payer, setup_program, ClientError, Config,
+    deployed_constants_account, execute_steps, initialize_client, initialize_instruction,
+    interact_with_program_instructions, send_and_confirm_transactions, setup_payer, setup_program,
+    ClientError, Config,
 };
 use solana_sdk::{
-    compute_budget::ComputeBudgetInstruction,
     instruction::{AccountMeta, Instruction},
     signature::Keypair,
     signer::Signer,
@@ -21,7 +21,10 @@ use swiftness_proof_parser::{json_parser, transform::TransformTo, StarkProof as
 use utils::AccountCast;
 use utils::BidirectionalStack;
 use utils::Executable;
-use verifier::{instruction::VerifierInstruction, state::BidirectionalStackAccount};
+use verifier::{
+    instruction::VerifierInstruction,
+    state::{BidirectionalStackAccount, VerificationState, DEFAULT_CAPACITY, DEFAULT_LENGTH_SIZE},
+};
 
 pub const CHUNK_SIZE: usize = 1000;
 
@@ -60,7 +63,10 @@ async fn main() -> client::Result<()> {
     );
 
     let create_account_tx = Transaction::new_signed_with_payer(
-        &[create_account_ix],
+        &[
+            create_account_ix,
+            initialize_instruction(&program_id, &stack_account.pubkey(), &payer.pubkey()),
+        ],
         Some(&payer.pubkey()),
         &[&payer, &stack_account],
         client.get_latest_blockhash().await?,
@@ -73,17 +79,23 @@ async fn main() -> client::Result<()> {
     println!("\nSet Proof on Solana");
     println!("====================");
 
-    let mut input: [u64; 2] = [0, 65536];
+    let mut input: [u64; 3] = [0, 65536, 0];
     let proof_bytes = cast_struct_to_slice(&mut input);
-    let new_offset = proof_bytes.len();
+    let new_offset = std::mem::offset_of!(BidirectionalStackAccount, proof);
     let stack_set_instructions = proof_bytes
         .chunks(CHUNK_SIZE)
         .enumerate()
         .map(|(i, chunk)| {
             Instruction::new_with_borsh(
                 program_id,
-                &VerifierInstruction::SetAccountData(i * CHUNK_SIZE, chunk.to_vec()),
-                vec![AccountMeta::new(stack_account.pubkey(), false)],
+                &VerifierInstruction::SetAccountData(
+                    std::mem::offset_of!(BidirectionalStackAccount, front_index) + i * CHUNK_SIZE,
+                    chunk.to_vec(),
+                ),
+                vec![
+                    AccountMeta::new(stack_account.pubkey(), false),
+                    AccountMeta::new_readonly(payer.pubkey(), true),
+                ],
             )
         })
         .collect::<Vec<_>>();
@@ -102,7 +114,10 @@ async fn main() -> client::Result<()> {
             Instruction::new_with_borsh(
                 program_id,
                 &VerifierInstruction::SetAccountData(new_offset + (i * CHUNK_SIZE), chunk.to_vec()),
-                vec![AccountMeta::new(stack_account.pubkey(), false)],
+                vec![
+                    AccountMeta::new(stack_account.pubkey(), false),
+                    AccountMeta::new_readonly(payer.pubkey(), true),
+                ],
             )
         })
         .collect::<Vec<_>>();
@@ -126,7 +141,10 @@ async fn main() -> client::Result<()> {
     let verify_public_input_ix = Instruction::new_with_borsh(
         program_id,
         &VerifierInstruction::PushTask(task.to_vec_with_type_tag()),
-        vec![AccountMeta::new(stack_account.pubkey(), false)],
+        vec![
+            AccountMeta::new(stack_account.pubkey(), false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
+        ],
     );
 
     let signature = interact_with_program_instructions(
@@ -139,8 +157,6 @@ async fn main() -> client::Result<()> {
     .await?;
     println!("Verify public input: {signature}");
 
-    let limit_instructions = ComputeBudgetInstruction::set_compute_unit_limit(800_000);
-
     let mut account_data = client
         .get_account_data(&stack_account.pubkey())
         .await
@@ -150,24 +166,20 @@ async fn main() -> client::Result<()> {
 
     println!("Simulation steps: {simulation_steps}");
 
-    let mut transactions = Vec::new();
-    for i in 0..simulation_steps {
-        // Execute the task
-        let execute_ix = Instruction::new_with_borsh(
-            program_id,
-            &VerifierInstruction::Execute(i as u32),
-            vec![AccountMeta::new(stack_account.pubkey(), false)],
-        );
-
-        let execute_tx = Transaction::new_signed_with_payer(
-            &[limit_instructions.clone(), execute_ix],
-            Some(&payer.pubkey()),
-            &[&payer],
-            client.get_latest_blockhash().await?,
-        );
-        transactions.push(execute_tx.clone());
-    }
-    send_and_confirm_transactions(&client, &transactions).await?;
+    let account_data = client
+        .get_account_data(&stack_account.pubkey())
+        .await
+        .map_err(ClientError::SolanaClientError)?;
+    execute_steps::<DEFAULT_CAPACITY, DEFAULT_LENGTH_SIZE>(
+        &client,
+        &payer,
+        &program_id,
+        &stack_account.pubkey(),
+        deployed_constants_account(&config).as_ref(),
+        account_data,
+        &config,
+    )
+    .await?;
 
     // Read and display the result
     let mut account_data = client
@@ -175,6 +187,9 @@ async fn main() -> client::Result<()> {
         .await
         .map_err(ClientError::SolanaClientError)?;
     let stack = BidirectionalStackAccount::cast_mut(&mut account_data);
+    println!("Verification state: {:?}", stack.status.state().unwrap());
+    assert_eq!(stack.status.state().unwrap(), VerificationState::Verified);
+
     let result_program_hash = Felt::from_bytes_be_slice(stack.borrow_front());
     stack.pop_front();
     let result_output_hash = Felt::from_bytes_be_slice(stack.borrow_front());
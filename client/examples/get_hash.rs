@@ -1,9 +1,9 @@
 use client::{
-    initialize_client, interact_with_program_instructions, send_and_confirm_transactions,
-    setup_payer, setup_program, ClientError, Config,
+    deployed_constants_account, execute_steps, initialize_client, initialize_instruction,
+    interact_with_program_instructions, send_and_confirm_transactions, setup_payer, setup_program,
+    ClientError, Config,
 };
 use solana_sdk::{
-    compute_budget::ComputeBudgetInstruction,
     instruction::{AccountMeta, Instruction},
     signature::{Keypair, Signer},
     transaction::Transaction,
@@ -11,10 +11,16 @@ use solana_sdk::{
 use solana_system_interface::instruction::create_account;
 use stark::{felt::Felt, swiftness::stark::types::cast_struct_to_slice};
 use starknet_crypto::{pedersen_hash, poseidon_hash_many, Felt as StarkFelt};
-use std::{mem::size_of, path::Path};
+use std::{
+    mem::{offset_of, size_of},
+    path::Path,
+};
 use swiftness_proof_parser::{json_parser, transform::TransformTo, StarkProof as StarkProofParser};
 use utils::{AccountCast, BidirectionalStack, Executable};
-use verifier::{instruction::VerifierInstruction, state::BidirectionalStackAccount};
+use verifier::{
+    instruction::VerifierInstruction,
+    state::{BidirectionalStackAccount, DEFAULT_CAPACITY, DEFAULT_LENGTH_SIZE},
+};
 
 use stark::stark_proof::get_hash::GetHash;
 
@@ -50,7 +56,10 @@ async fn main() -> client::Result<()> {
     );
 
     let create_account_tx = Transaction::new_signed_with_payer(
-        &[create_account_ix],
+        &[
+            create_account_ix,
+            initialize_instruction(&program_id, &stack_account.pubkey(), &payer.pubkey()),
+        ],
         Some(&payer.pubkey()),
         &[&payer, &stack_account],
         client.get_latest_blockhash().await?,
@@ -61,13 +70,19 @@ async fn main() -> client::Result<()> {
         .await?;
     println!("Account created successfully: {signature}");
 
-    let mut stack_init_input: [u64; 2] = [0, 65536];
+    let mut stack_init_input: [u64; 3] = [0, 65536, 0];
     let stack_init_bytes = cast_struct_to_slice(&mut stack_init_input);
 
     let init_ix = Instruction::new_with_borsh(
         program_id,
-        &VerifierInstruction::SetAccountData(0, stack_init_bytes.to_vec()),
-        vec![AccountMeta::new(stack_account.pubkey(), false)],
+        &VerifierInstruction::SetAccountData(
+            offset_of!(BidirectionalStackAccount, front_index),
+            stack_init_bytes.to_vec(),
+        ),
+        vec![
+            AccountMeta::new(stack_account.pubkey(), false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
+        ],
     );
 
     let signature = interact_with_program_instructions(
@@ -89,24 +104,37 @@ async fn main() -> client::Result<()> {
 
     let mut proof_verifier = proof.transform_to();
 
-    let mut stack_init_input: [u64; 2] = [0, 65536];
+    let mut stack_init_input: [u64; 3] = [0, 65536, 0];
     let stack_init_bytes = cast_struct_to_slice(&mut stack_init_input);
+    let proof_offset = offset_of!(BidirectionalStackAccount, proof);
     let proof_bytes = cast_struct_to_slice(&mut proof_verifier).to_vec();
-    let mut init_calldata = stack_init_bytes.to_vec();
-    init_calldata.extend(proof_bytes.clone());
 
-    println!("Proof bytes in kb: {:?}", init_calldata.len() / 1024);
-    let instructions = init_calldata
+    println!("Proof bytes in kb: {:?}", proof_bytes.len() / 1024);
+    let mut instructions = proof_bytes
         .chunks(CHUNK_SIZE)
         .enumerate()
         .map(|(i, chunk)| {
             Instruction::new_with_borsh(
                 program_id,
-                &VerifierInstruction::SetAccountData(i * CHUNK_SIZE, chunk.to_vec()),
-                vec![AccountMeta::new(stack_account.pubkey(), false)],
+                &VerifierInstruction::SetAccountData(proof_offset + i * CHUNK_SIZE, chunk.to_vec()),
+                vec![
+                    AccountMeta::new(stack_account.pubkey(), false),
+                    AccountMeta::new_readonly(payer.pubkey(), true),
+                ],
             )
         })
         .collect::<Vec<_>>();
+    instructions.push(Instruction::new_with_borsh(
+        program_id,
+        &VerifierInstruction::SetAccountData(
+            offset_of!(BidirectionalStackAccount, front_index),
+            stack_init_bytes.to_vec(),
+        ),
+        vec![
+            AccountMeta::new(stack_account.pubkey(), false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
+        ],
+    ));
 
     println!("Instructions number: {:?}", instructions.len());
     let mut transactions = Vec::new();
@@ -135,7 +163,10 @@ async fn main() -> client::Result<()> {
     let push_task_ix = Instruction::new_with_borsh(
         program_id,
         &VerifierInstruction::PushTask(get_hash_task.to_vec_with_type_tag()),
-        vec![AccountMeta::new(stack_account.pubkey(), false)],
+        vec![
+            AccountMeta::new(stack_account.pubkey(), false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
+        ],
     );
 
     let signature = interact_with_program_instructions(
@@ -157,43 +188,20 @@ async fn main() -> client::Result<()> {
     let simulation_steps = stack.simulate();
     println!("Steps in simulation: {simulation_steps}");
 
-    let limit_instructions = ComputeBudgetInstruction::set_compute_unit_limit(800_000);
-
-    // Execute all steps until task is complete - split into chunks of max 5000
-    const MAX_CHUNK_SIZE: usize = 5000;
-
-    let simulation_steps_usize = simulation_steps as usize;
-
-    for chunk_start in (0..simulation_steps_usize).step_by(MAX_CHUNK_SIZE) {
-        let chunk_end = std::cmp::min(chunk_start + MAX_CHUNK_SIZE, simulation_steps_usize);
-        let chunk_size = chunk_end - chunk_start;
-
-        println!(
-            "Processing steps {}-{} ({} steps)",
-            chunk_start,
-            chunk_end - 1,
-            chunk_size
-        );
-
-        let mut transactions = Vec::new();
-        for i in chunk_start..chunk_end {
-            let execute_ix = Instruction::new_with_borsh(
-                program_id,
-                &VerifierInstruction::Execute(i as u32),
-                vec![AccountMeta::new(stack_account.pubkey(), false)],
-            );
-            let execute_tx = Transaction::new_signed_with_payer(
-                &[limit_instructions.clone(), execute_ix],
-                Some(&payer.pubkey()),
-                &[&payer],
-                client.get_latest_blockhash().await?,
-            );
-            transactions.push(execute_tx.clone());
-        }
-
-        send_and_confirm_transactions(&client, &transactions).await?;
-        println!("Chunk {}-{} completed", chunk_start, chunk_end - 1);
-    }
+    let account_data = client
+        .get_account_data(&stack_account.pubkey())
+        .await
+        .map_err(ClientError::SolanaClientError)?;
+    execute_steps::<DEFAULT_CAPACITY, DEFAULT_LENGTH_SIZE>(
+        &client,
+        &payer,
+        &program_id,
+        &stack_account.pubkey(),
+        deployed_constants_account(&config).as_ref(),
+        account_data,
+        &config,
+    )
+    .await?;
 
     println!("All execution steps completed");
 
@@ -1,6 +1,6 @@
 use client::{
-    initialize_client, interact_with_program_instructions, send_and_confirm_transactions,
-    setup_payer, setup_program, ClientError, Config,
+    deployed_constants_account, execute_steps, initialize_client, initialize_instruction,
+    interact_with_program_instructions, setup_payer, setup_program, ClientError, Config,
 };
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
@@ -12,7 +12,10 @@ use stark::poseidon::PoseidonHashMany;
 use stark::{felt::Felt, swiftness::stark::types::cast_struct_to_slice};
 use std::{mem::size_of, path::Path};
 use utils::{AccountCast, BidirectionalStack, Executable};
-use verifier::{instruction::VerifierInstruction, state::BidirectionalStackAccount};
+use verifier::{
+    instruction::VerifierInstruction,
+    state::{BidirectionalStackAccount, DEFAULT_CAPACITY, DEFAULT_LENGTH_SIZE},
+};
 
 /// Main entry point for the Solana program client
 #[tokio::main]
@@ -54,7 +57,10 @@ async fn main() -> client::Result<()> {
 
     // Create and send the transaction
     let create_account_tx = Transaction::new_signed_with_payer(
-        &[create_account_ix],
+        &[
+            create_account_ix,
+            initialize_instruction(&program_id, &stack_account.pubkey(), &payer.pubkey()),
+        ],
         Some(&payer.pubkey()),
         &[&payer, &stack_account],
         client.get_latest_blockhash().await?,
@@ -66,13 +72,19 @@ async fn main() -> client::Result<()> {
     println!("Account created successfully: {signature}");
     // Cast to stack account to see if initialized correctly
 
-    let mut stack_init_input: [u64; 2] = [0, 65536];
+    let mut stack_init_input: [u64; 3] = [0, 65536, 0];
     let stack_init_bytes = cast_struct_to_slice(&mut stack_init_input);
     // Initialize the account
     let init_ix = Instruction::new_with_borsh(
         program_id,
-        &VerifierInstruction::SetAccountData(0, stack_init_bytes.to_vec()),
-        vec![AccountMeta::new(stack_account.pubkey(), false)],
+        &VerifierInstruction::SetAccountData(
+            std::mem::offset_of!(BidirectionalStackAccount, front_index),
+            stack_init_bytes.to_vec(),
+        ),
+        vec![
+            AccountMeta::new(stack_account.pubkey(), false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
+        ],
     );
 
     let signature = interact_with_program_instructions(
@@ -128,7 +140,10 @@ async fn main() -> client::Result<()> {
         let push_data_ix = Instruction::new_with_borsh(
             program_id,
             &VerifierInstruction::PushData(input.to_bytes_be().to_vec()),
-            vec![AccountMeta::new(stack_account.pubkey(), false)],
+            vec![
+                AccountMeta::new(stack_account.pubkey(), false),
+                AccountMeta::new_readonly(payer.pubkey(), true),
+            ],
         );
 
         let push_data_tx = Transaction::new_signed_with_payer(
@@ -147,7 +162,10 @@ async fn main() -> client::Result<()> {
         let push_data_ix = Instruction::new_with_borsh(
             program_id,
             &VerifierInstruction::PushData(Felt::ZERO.to_bytes_be().to_vec()),
-            vec![AccountMeta::new(stack_account.pubkey(), false)],
+            vec![
+                AccountMeta::new(stack_account.pubkey(), false),
+                AccountMeta::new_readonly(payer.pubkey(), true),
+            ],
         );
 
         let push_data_tx = Transaction::new_signed_with_payer(
@@ -167,7 +185,10 @@ async fn main() -> client::Result<()> {
     let push_task_ix = Instruction::new_with_borsh(
         program_id,
         &VerifierInstruction::PushTask(poseidon_task.to_vec_with_type_tag()),
-        vec![AccountMeta::new(stack_account.pubkey(), false)],
+        vec![
+            AccountMeta::new(stack_account.pubkey(), false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
+        ],
     );
 
     let signature = interact_with_program_instructions(
@@ -189,22 +210,20 @@ async fn main() -> client::Result<()> {
     let simulation_steps = stack.simulate();
     println!("Steps in simulation: {simulation_steps}");
     // Execute until task is complete
-    let mut transactions = Vec::new();
-    for i in 0..simulation_steps {
-        let execute_ix = Instruction::new_with_borsh(
-            program_id,
-            &VerifierInstruction::Execute(i as u32),
-            vec![AccountMeta::new(stack_account.pubkey(), false)],
-        );
-        let execute_tx = Transaction::new_signed_with_payer(
-            &[execute_ix],
-            Some(&payer.pubkey()),
-            &[&payer],
-            client.get_latest_blockhash().await?,
-        );
-        transactions.push(execute_tx.clone());
-    }
-    send_and_confirm_transactions(&client, &transactions).await?;
+    let account_data = client
+        .get_account_data(&stack_account.pubkey())
+        .await
+        .map_err(ClientError::SolanaClientError)?;
+    execute_steps::<DEFAULT_CAPACITY, DEFAULT_LENGTH_SIZE>(
+        &client,
+        &payer,
+        &program_id,
+        &stack_account.pubkey(),
+        deployed_constants_account(&config).as_ref(),
+        account_data,
+        &config,
+    )
+    .await?;
     // Read and display the result
     let mut account_data = client
         .get_account_data(&stack_account.pubkey())
@@ -1,6 +1,6 @@
 use client::{
-    initialize_client, send_and_confirm_transactions, setup_payer, setup_program, ClientError,
-    Config,
+    initialize_client, initialize_instruction, send_and_confirm_transactions, setup_payer,
+    setup_program, ClientError, Config,
 };
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
@@ -10,7 +10,7 @@ use solana_sdk::{
 };
 use solana_system_interface::instruction::create_account;
 use stark::swiftness::stark::types::{cast_struct_to_slice, StarkProof};
-use std::path::Path;
+use std::{mem::offset_of, path::Path};
 use swiftness_proof_parser::{json_parser, transform::TransformTo, StarkProof as StarkProofParser};
 use utils::AccountCast;
 use utils::BidirectionalStack;
@@ -54,7 +54,10 @@ async fn main() -> client::Result<()> {
     );
 
     let create_account_tx = Transaction::new_signed_with_payer(
-        &[create_account_ix],
+        &[
+            create_account_ix,
+            initialize_instruction(&program_id, &stack_account.pubkey(), &payer.pubkey()),
+        ],
         Some(&payer.pubkey()),
         &[&payer, &stack_account],
         client.get_latest_blockhash().await?,
@@ -71,24 +74,37 @@ async fn main() -> client::Result<()> {
     let proof = StarkProofParser::try_from(proof_json).unwrap();
 
     let mut proof_verifier = proof.transform_to();
-    let mut stack_init_input: [u64; 2] = [0, 65536];
+    let mut stack_init_input: [u64; 3] = [0, 65536, 0];
     let stack_init_bytes = cast_struct_to_slice(&mut stack_init_input);
+    let proof_offset = offset_of!(BidirectionalStackAccount, proof);
     let proof_bytes = cast_struct_to_slice(&mut proof_verifier).to_vec();
-    let mut init_calldata = stack_init_bytes.to_vec();
-    init_calldata.extend(proof_bytes.clone());
 
-    println!("Proof bytes in kb: {:?}", init_calldata.len() / 1024);
-    let instructions = init_calldata
+    println!("Proof bytes in kb: {:?}", proof_bytes.len() / 1024);
+    let mut instructions = proof_bytes
         .chunks(CHUNK_SIZE)
         .enumerate()
         .map(|(i, chunk)| {
             Instruction::new_with_borsh(
                 program_id,
-                &VerifierInstruction::SetAccountData(i * CHUNK_SIZE, chunk.to_vec()),
-                vec![AccountMeta::new(stack_account.pubkey(), false)],
+                &VerifierInstruction::SetAccountData(proof_offset + i * CHUNK_SIZE, chunk.to_vec()),
+                vec![
+                    AccountMeta::new(stack_account.pubkey(), false),
+                    AccountMeta::new_readonly(payer.pubkey(), true),
+                ],
             )
         })
         .collect::<Vec<_>>();
+    instructions.push(Instruction::new_with_borsh(
+        program_id,
+        &VerifierInstruction::SetAccountData(
+            offset_of!(BidirectionalStackAccount, front_index),
+            stack_init_bytes.to_vec(),
+        ),
+        vec![
+            AccountMeta::new(stack_account.pubkey(), false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
+        ],
+    ));
 
     println!("Instructions number: {:?}", instructions.len());
     let mut transactions = Vec::new();
@@ -1,4 +1,5 @@
 use clap::Parser;
+use solana_sdk::pubkey::Pubkey;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -59,3 +60,76 @@ impl Config {
         Duration::from_secs(self.rpc_timeout_secs)
     }
 }
+
+/// Configuration of the `verify` subcommand
+#[derive(Parser, Debug)]
+pub struct VerifyConfig {
+    #[clap(flatten)]
+    pub config: Config,
+
+    /// Path to the proof JSON file, defaults to the bundled example proof
+    #[clap(long)]
+    pub proof: Option<PathBuf>,
+
+    /// Verifier program id, defaults to the program deployed from the keypairs directory
+    #[clap(long)]
+    pub program_id: Option<Pubkey>,
+
+    /// Existing stack account to verify with, a new one is created when omitted
+    #[clap(long)]
+    pub account: Option<Pubkey>,
+
+    /// Reset and reuse a rejected or completed stack account from the
+    /// keypairs directory instead of creating a new one
+    #[clap(long, conflicts_with = "account")]
+    pub account_pool: bool,
+
+    /// Create or pool stack accounts with the large layout, for proofs whose
+    /// stack does not fit in the default account
+    #[clap(long, conflicts_with = "account")]
+    pub large_stack: bool,
+
+    /// Constants account passed to every step, defaults to the one created by
+    /// `deploy` in the keypairs directory
+    #[clap(long)]
+    pub constants_account: Option<Pubkey>,
+}
+
+/// Configuration of the `simulate` subcommand
+#[derive(Parser, Debug)]
+pub struct SimulateConfig {
+    /// Path to the compiled verifier program
+    #[clap(long, default_value = "target/deploy/verifier.so")]
+    pub program: PathBuf,
+
+    /// Path to the proof JSON file, defaults to the bundled example proof
+    #[clap(long)]
+    pub proof: Option<PathBuf>,
+}
+
+/// Configuration of the `profile` subcommand
+#[derive(Parser, Debug)]
+pub struct ProfileConfig {
+    /// Path to the verifier program compiled with the `profile` feature
+    #[clap(long, default_value = "target/deploy/verifier.so")]
+    pub program: PathBuf,
+
+    /// Path to the proof JSON file, defaults to the bundled example proof
+    #[clap(long)]
+    pub proof: Option<PathBuf>,
+
+    /// Aggregate an existing transaction log file instead of simulating
+    #[clap(long)]
+    pub logs: Option<PathBuf>,
+}
+
+/// Configuration of the `inspect-account` subcommand
+#[derive(Parser, Debug)]
+pub struct InspectAccountConfig {
+    #[clap(flatten)]
+    pub config: Config,
+
+    /// Stack account to inspect
+    #[clap(long)]
+    pub account: Pubkey,
+}
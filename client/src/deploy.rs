@@ -1,14 +1,14 @@
 use std::path::Path;
 
-use solana_sdk::{
-    signature::{write_keypair_file, Keypair},
-    signer::Signer,
-    transaction::Transaction,
-};
-use verifier::state::BidirectionalStackAccount;
+use solana_sdk::{signature::Keypair, signer::Signer};
 
-use crate::{initialize_client, setup_payer, setup_program, Config, Result};
+use crate::{
+    create_constants_account, create_stack_account, initialize_client, pedersen_constants,
+    read_keypair_file, setup_payer, setup_program, write_keypair_file, Config, Result,
+    CONSTANTS_ACCOUNT_KEYPAIR, PEDERSEN_WINDOW_BITS,
+};
 use log::info;
+use verifier::state::BidirectionalStackAccount;
 
 pub async fn deploy(config: &Config) -> Result<()> {
     let client = initialize_client(config).await?;
@@ -29,30 +29,46 @@ pub async fn deploy(config: &Config) -> Result<()> {
         config.keypairs_dir.join("stack-account-keypair.json"),
     )
     .unwrap();
-    info!(public_key:% = stack_account.pubkey(); "Creating new account");
 
-    let space = size_of::<BidirectionalStackAccount>();
-    info!(size_in_bytes:% = space; "Account space");
-
-    let create_account_ix = solana_system_interface::instruction::create_account(
-        &payer.pubkey(),
-        &stack_account.pubkey(),
-        client.get_minimum_balance_for_rent_exemption(space).await?,
-        space as u64,
+    let signature = create_stack_account(
+        &client,
+        &payer,
         &program_id,
-    );
+        &stack_account,
+        size_of::<BidirectionalStackAccount>(),
+    )
+    .await?;
 
-    let create_account_tx = Transaction::new_signed_with_payer(
-        &[create_account_ix],
-        Some(&payer.pubkey()),
-        &[&payer, &stack_account],
-        client.get_latest_blockhash().await?,
-    );
+    info!(signature:% = signature; "Account created successfully");
 
-    let signature = client
-        .send_and_confirm_transaction(&create_account_tx)
-        .await?;
+    // The program reads the Pedersen tables from the constants account. The
+    // keypair is written first, a later deploy resumes from the account as it
+    // is on-chain
+    let constants_keypair_path = config.keypairs_dir.join(CONSTANTS_ACCOUNT_KEYPAIR);
+    let constants_account = if constants_keypair_path.exists() {
+        read_keypair_file(&constants_keypair_path)?
+    } else {
+        let keypair = Keypair::new();
+        write_keypair_file(&keypair, &constants_keypair_path)?;
+        keypair
+    };
+    let signature = create_constants_account(
+        &client,
+        &payer,
+        &program_id,
+        &constants_account,
+        &pedersen_constants(PEDERSEN_WINDOW_BITS),
+        config,
+    )
+    .await?;
 
-    info!(signature:% = signature; "Account created successfully");
+    match signature {
+        Some(signature) => {
+            info!(public_key:% = constants_account.pubkey(), signature:% = signature; "Constants account frozen")
+        }
+        None => {
+            info!(public_key:% = constants_account.pubkey(); "Constants account already frozen")
+        }
+    }
     Ok(())
 }
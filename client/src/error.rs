@@ -31,6 +31,12 @@ pub enum ClientError {
     #[error("Failed to connect to validator: {0}")]
     ConnectionError(String),
 
+    #[error("Stack account error: {0}")]
+    AccountError(String),
+
+    #[error("Proof rejected: {0}")]
+    ProofRejected(String),
+
     #[error("Serialization error: {0}")]
     SerializationError(String),
 }
@@ -0,0 +1,219 @@
+use std::fmt::Write;
+
+use stark::felt::Felt;
+use utils::{
+    frame::{FRAME_MARKER_SIZE, FRAME_MARKER_UNSET},
+    AccountCast,
+};
+use verifier::{
+    scheduler::{debug_task, task_type_name},
+    state::{
+        account_version, BidirectionalStackAccount, LargeStackAccount, StackAccount,
+        ACCOUNT_VERSION,
+    },
+};
+
+use crate::{initialize_client, utils::reject_reason, ClientError, InspectAccountConfig, Result};
+
+pub async fn inspect_account(inspect_config: &InspectAccountConfig) -> Result<()> {
+    let client = initialize_client(&inspect_config.config).await?;
+
+    let account_data = client
+        .get_account_data(&inspect_config.account)
+        .await
+        .map_err(ClientError::SolanaClientError)?;
+    let size = account_data.len();
+    if size != size_of::<BidirectionalStackAccount>() && size != size_of::<LargeStackAccount>() {
+        return Err(ClientError::AccountError(format!(
+            "Account {} is not a stack account, size {} bytes",
+            inspect_config.account,
+            account_data.len()
+        )));
+    }
+    match account_version(&account_data) {
+        Some(ACCOUNT_VERSION) => {}
+        Some(version) => {
+            return Err(ClientError::AccountError(format!(
+                "Account {} has layout version {version}, expected {ACCOUNT_VERSION}",
+                inspect_config.account
+            )))
+        }
+        None => {
+            return Err(ClientError::AccountError(format!(
+                "Account {} was not initialized",
+                inspect_config.account
+            )))
+        }
+    }
+
+    let description = if size == size_of::<LargeStackAccount>() {
+        describe_stack(LargeStackAccount::cast(&account_data))
+    } else {
+        describe_stack(BidirectionalStackAccount::cast(&account_data))
+    };
+    print!("{description}");
+    Ok(())
+}
+
+/// Human-readable dump of the status and both stacks of a stack account.
+/// Items are read in place within the buffer, so the dump of a corrupted
+/// account ends at its first malformed item instead of panicking.
+pub fn describe_stack<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+    stack: &StackAccount<CAPACITY, LENGTH_SIZE>,
+) -> String {
+    let mut out = String::new();
+    writeln!(out, "Front index: {}", stack.front_index).unwrap();
+    writeln!(out, "Back index: {}", stack.back_index).unwrap();
+    writeln!(out, "Executed steps: {}", stack.executed_steps).unwrap();
+
+    let status = stack.status;
+    match status.state() {
+        Ok(state) => writeln!(out, "State: {state:?}").unwrap(),
+        Err(_) => writeln!(out, "State: invalid ({})", status.state).unwrap(),
+    }
+    if status.root_task != 0 {
+        writeln!(
+            out,
+            "Verification root: {}",
+            describe_type_tag(status.root_task)
+        )
+        .unwrap();
+    }
+    if status.error_code != 0 {
+        let reason = reject_reason(status.error_code).unwrap_or_else(|| "unknown".to_string());
+        writeln!(
+            out,
+            "Rejected with code {} ({reason}) by {}",
+            status.error_code,
+            describe_type_tag(status.failed_task)
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "\nTasks (back stack, next first):").unwrap();
+    let mut offset = stack.back_index;
+    let mut index = 0;
+    while offset != CAPACITY {
+        let Some((entry, next)) = back_item::<LENGTH_SIZE>(&stack.buffer, offset) else {
+            writeln!(out, "  malformed item at offset {offset}").unwrap();
+            break;
+        };
+        writeln!(out, "  [{index}] {}", describe_task(entry)).unwrap();
+        offset = next;
+        index += 1;
+    }
+
+    writeln!(out, "\nData (front stack, top first):").unwrap();
+    let mut offset = stack.front_index;
+    let mut index = 0;
+    while offset != 0 {
+        let Some((data, next)) = front_item::<LENGTH_SIZE>(&stack.buffer, offset) else {
+            writeln!(out, "  malformed item at offset {offset}").unwrap();
+            break;
+        };
+        let description = if data.len() == 32 {
+            format!("felt {}", Felt::from_bytes_be_slice(data))
+        } else {
+            format!("{} bytes: {}", data.len(), to_hex(data))
+        };
+        writeln!(out, "  [{index}] {description}").unwrap();
+        offset = next;
+        index += 1;
+    }
+
+    out
+}
+
+/// Back stack item whose length prefix starts at `offset`, and the offset of
+/// the item below it, `None` if the item does not fit in `buffer`
+fn back_item<const LENGTH_SIZE: usize>(buffer: &[u8], offset: usize) -> Option<(&[u8], usize)> {
+    let start = offset.checked_add(LENGTH_SIZE)?;
+    let length = read_length(buffer.get(offset..start)?.iter());
+    let end = start.checked_add(length)?;
+    Some((buffer.get(start..end)?, end))
+}
+
+/// Front stack item whose length suffix ends at `offset`, and the offset of
+/// the item below it, `None` if the item does not fit in `buffer`
+fn front_item<const LENGTH_SIZE: usize>(buffer: &[u8], offset: usize) -> Option<(&[u8], usize)> {
+    let end = offset.checked_sub(LENGTH_SIZE)?;
+    let length = read_length(buffer.get(end..offset)?.iter().rev());
+    let start = end.checked_sub(length)?;
+    Some((buffer.get(start..end)?, start))
+}
+
+/// Item length stored most significant byte first
+fn read_length<'a>(bytes: impl Iterator<Item = &'a u8>) -> usize {
+    bytes.fold(0, |length, byte| (length << 8) | usize::from(*byte))
+}
+
+/// Task of a back stack entry and the frame marker it is stored with
+fn describe_task(entry: &[u8]) -> String {
+    let Some(marker_start) = entry.len().checked_sub(FRAME_MARKER_SIZE) else {
+        return format!("{} bytes: {}", entry.len(), to_hex(entry));
+    };
+    let (task, marker) = entry.split_at(marker_start);
+    let description = match task.get(0..4) {
+        Some(type_tag) => {
+            let type_tag = u32::from_be_bytes(type_tag.try_into().unwrap());
+            let state = debug_task(task)
+                .unwrap_or_else(|| format!("{} bytes: {}", task.len(), to_hex(task)));
+            format!("{} {state}", describe_type_tag(type_tag))
+        }
+        None => format!("{} bytes: {}", task.len(), to_hex(task)),
+    };
+    match u32::from_be_bytes(marker.try_into().unwrap()) {
+        FRAME_MARKER_UNSET => format!("{description}, frame not opened"),
+        base => format!("{description}, frame at front offset {base}"),
+    }
+}
+
+fn describe_type_tag(type_tag: u32) -> String {
+    match task_type_name(type_tag) {
+        Some(name) => name.to_string(),
+        None => format!("unknown task {type_tag:#010x}"),
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arithmetic::add::Add;
+    use utils::Scheduler;
+
+    #[test]
+    fn describe_stack_decodes_tasks_and_felts() {
+        let mut stack = BidirectionalStackAccount::default();
+        stack.push_task(Add::new(48, 52));
+        stack.push_data(&Felt::from(7u64).to_bytes_be());
+        stack.push_data(&[1, 2, 3]);
+
+        let description = describe_stack(&stack);
+        assert!(
+            description.contains("[0] arithmetic::add::Add Add { x: 48, y: 52 }, frame not opened")
+        );
+        assert!(description.contains("[0] 3 bytes: 010203"));
+        assert!(description.contains("[1] felt 7"));
+    }
+
+    #[test]
+    fn describe_stack_stops_at_malformed_items() {
+        let mut stack = BidirectionalStackAccount::default();
+        stack.push_task(Add::new(48, 52));
+        stack.push_data(&[1, 2, 3]);
+
+        // Length prefixes pointing past the buffer
+        let back_index = stack.back_index;
+        stack.buffer[back_index] = 0xff;
+        let front_index = stack.front_index;
+        stack.buffer[front_index - 1] = 0xff;
+
+        let description = describe_stack(&stack);
+        assert!(description.contains(&format!("malformed item at offset {back_index}")));
+        assert!(description.contains(&format!("malformed item at offset {front_index}")));
+    }
+}
@@ -4,10 +4,13 @@ pub mod config;
 pub mod error;
 pub mod utils;
 
-pub use config::Config;
+pub use config::{Config, InspectAccountConfig, ProfileConfig, SimulateConfig, VerifyConfig};
 pub use error::{ClientError, Result};
 pub use utils::*;
 
 pub mod deploy;
+pub mod inspect;
+pub mod profile;
 pub mod retrive_funds;
+pub mod simulate;
 pub mod verify;
@@ -1,5 +1,8 @@
 use clap::{Parser, Subcommand};
-use client::{deploy, retrive_funds, verify, Config};
+use client::{
+    deploy, inspect, profile, retrive_funds, simulate, verify, Config, InspectAccountConfig,
+    ProfileConfig, SimulateConfig, VerifyConfig,
+};
 
 #[derive(Debug, Parser)]
 #[clap(about, version)]
@@ -10,8 +13,14 @@ struct Cli {
 
 #[derive(Debug, Subcommand)]
 enum Subcommands {
-    /// Verify a proof using existing account and programs
-    Verify(Config),
+    /// Verify a proof using the deployed program
+    Verify(VerifyConfig),
+    /// Verify a proof against a local build of the program without a validator
+    Simulate(SimulateConfig),
+    /// Report the compute units consumed per task type
+    Profile(ProfileConfig),
+    /// Print the status, pending tasks and data of a stack account
+    InspectAccount(InspectAccountConfig),
     /// Deploy a new program to the solana and create a new account
     Deploy(Config),
     /// Retrive funds from the solana (close the account)
@@ -25,6 +34,9 @@ async fn main() -> client::Result<()> {
     let cli = Cli::parse();
     match cli.command {
         Subcommands::Verify(config) => verify::verify(&config).await?,
+        Subcommands::Simulate(config) => simulate::simulate(&config).await?,
+        Subcommands::Profile(config) => profile::profile(&config).await?,
+        Subcommands::InspectAccount(config) => inspect::inspect_account(&config).await?,
         Subcommands::Deploy(config) => deploy::deploy(&config).await?,
         Subcommands::RetriveFunds(config) => retrive_funds::retrive_funds(&config).await?,
     }
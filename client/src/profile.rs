@@ -0,0 +1,127 @@
+use std::{collections::BTreeMap, fs};
+
+use log::info;
+use verifier::PROFILE_LOG_PREFIX;
+
+use crate::{read_proof, simulate, ProfileConfig, Result};
+
+/// Compute units consumed by all executions of a single task type
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TaskProfile {
+    /// Number of times the task was executed
+    pub calls: u64,
+    /// Total compute units consumed over all executions
+    pub total: u64,
+    /// Highest compute units consumed by a single execution
+    pub max: u64,
+}
+
+impl TaskProfile {
+    pub fn average(&self) -> u64 {
+        self.total.checked_div(self.calls).unwrap_or_default()
+    }
+}
+
+/// Aggregate the per-task log lines emitted by a verifier built with the
+/// `profile` feature, keyed by task name. Unrelated lines are ignored.
+pub fn aggregate_profile<'a>(
+    logs: impl IntoIterator<Item = &'a str>,
+) -> BTreeMap<String, TaskProfile> {
+    let mut profile = BTreeMap::<String, TaskProfile>::new();
+
+    for line in logs {
+        let Some((_, entry)) = line.split_once(&format!("Program log: {PROFILE_LOG_PREFIX} "))
+        else {
+            continue;
+        };
+        let mut parts = entry.split_whitespace();
+        let (Some(task), Some(Ok(units)), None) = (
+            parts.next(),
+            parts.next().map(str::parse::<u64>),
+            parts.next(),
+        ) else {
+            continue;
+        };
+
+        let task_profile = profile.entry(task.to_string()).or_default();
+        task_profile.calls += 1;
+        task_profile.total += units;
+        task_profile.max = task_profile.max.max(units);
+    }
+
+    profile
+}
+
+/// Run the `profile` subcommand
+pub async fn profile(config: &ProfileConfig) -> Result<()> {
+    let logs = match config.logs {
+        Some(ref path) => fs::read_to_string(path)?
+            .lines()
+            .map(str::to_string)
+            .collect(),
+        None => {
+            let proof = read_proof(config.proof.as_deref())?;
+            simulate::run(&config.program, &proof).await?.logs
+        }
+    };
+
+    let profile = aggregate_profile(logs.iter().map(String::as_str));
+    if profile.is_empty() {
+        info!(
+            "No profiling logs found, build the program with `cargo build-sbf --features profile`"
+        );
+        return Ok(());
+    }
+
+    let total: u64 = profile.values().map(|task| task.total).sum();
+    let mut tasks: Vec<_> = profile.into_iter().collect();
+    tasks.sort_by(|(_, a), (_, b)| b.total.cmp(&a.total));
+
+    println!(
+        "{:<32} {:>8} {:>14} {:>10} {:>10} {:>7}",
+        "Task", "Calls", "Total CU", "Avg CU", "Max CU", "Share"
+    );
+    for (task, task_profile) in tasks {
+        println!(
+            "{:<32} {:>8} {:>14} {:>10} {:>10} {:>6.2}%",
+            task,
+            task_profile.calls,
+            task_profile.total,
+            task_profile.average(),
+            task_profile.max,
+            task_profile.total as f64 * 100.0 / total as f64
+        );
+    }
+    println!("{:<32} {:>8} {:>14}", "Total", "", total);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregate_profile_sums_per_task() {
+        let logs = [
+            "Program log: profile HadesPermutation 1200",
+            "Program log: profile HadesPermutation 1000",
+            "Program log: profile PoseidonHashMany 300",
+            "Program log: Instruction: Execute",
+            "Program log: profile Malformed",
+        ];
+        let profile = aggregate_profile(logs);
+
+        assert_eq!(profile.len(), 2);
+        assert_eq!(
+            profile["HadesPermutation"],
+            TaskProfile {
+                calls: 2,
+                total: 2200,
+                max: 1200
+            }
+        );
+        assert_eq!(profile["HadesPermutation"].average(), 1100);
+        assert_eq!(profile["PoseidonHashMany"].calls, 1);
+    }
+}
@@ -5,7 +5,6 @@ use solana_sdk::{
     signer::{EncodableKey, Signer},
     transaction::Transaction,
 };
-use solana_system_interface::program::ID as SYSTEM_PROGRAM_ID;
 use verifier::instruction::VerifierInstruction;
 
 use crate::{initialize_client, setup_payer, Config, Result};
@@ -32,20 +31,22 @@ pub async fn retrive_funds(config: &Config) -> Result<()> {
     let balance_sol = balance as f64 / LAMPORTS_PER_SOL as f64;
     info!(balance_sol:% = balance_sol; "Balance");
 
+    // The payer is the authority recorded when the account was created and
+    // gets the lamports back
     let close_account_ix = Instruction::new_with_borsh(
         program_id,
         &VerifierInstruction::Close,
         vec![
-            AccountMeta::new(stack_account.pubkey(), true),
+            AccountMeta::new(stack_account.pubkey(), false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
             AccountMeta::new(payer.pubkey(), false),
-            AccountMeta::new(SYSTEM_PROGRAM_ID, false),
         ],
     );
 
     let close_account_tx = Transaction::new_signed_with_payer(
         &[close_account_ix],
         Some(&payer.pubkey()),
-        &[&stack_account, &payer],
+        &[&payer],
         client.get_latest_blockhash().await?,
     );
     let close_account_signature = client
@@ -0,0 +1,267 @@
+use std::{fs, path::Path};
+
+use log::{info, trace};
+use solana_program_test::{ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    bpf_loader,
+    compute_budget::ComputeBudgetInstruction,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::Keypair,
+    signer::Signer,
+    transaction::Transaction,
+};
+use stark::{
+    felt::Felt,
+    stark_proof::VerifyPublicInput,
+    swiftness::stark::types::{cast_struct_to_slice, StarkProof},
+};
+use utils::{AccountCast, Executable};
+use verifier::{
+    instruction::VerifierInstruction,
+    state::{BidirectionalStackAccount, ConstantsAccount, CONSTANTS_HEADER, DEFAULT_CAPACITY},
+};
+
+use crate::{
+    initialize_instruction, pedersen_constants, read_proof, set_account_data_instructions,
+    verification_result,
+    verify::{CHUNK_SIZE, EXECUTE_COMPUTE_UNITS, SET_ACCOUNT_DATA_COMPUTE_UNITS},
+    ClientError, Result, SimulateConfig, PEDERSEN_WINDOW_BITS,
+};
+
+/// Outcome of a verification executed in the local program test runtime
+#[derive(Debug)]
+pub struct SimulationReport {
+    /// Number of `Execute` transactions it took to empty the task stack
+    pub steps: u128,
+    /// Highest compute units consumed by a single `Execute` transaction
+    pub max_compute_units: u64,
+    /// Total compute units consumed by all `Execute` transactions
+    pub total_compute_units: u64,
+    /// Program logs of all `Execute` transactions
+    pub logs: Vec<String>,
+    pub program_hash: Felt,
+    pub output_hash: Felt,
+}
+
+/// Run the `simulate` subcommand
+pub async fn simulate(config: &SimulateConfig) -> Result<()> {
+    let proof = read_proof(config.proof.as_deref())?;
+    let report = run(&config.program, &proof).await?;
+
+    info!(steps:% = report.steps; "Simulation steps");
+    info!(max_compute_units:% = report.max_compute_units; "Max compute units per step");
+    info!(total_compute_units:% = report.total_compute_units; "Total compute units");
+    info!(result_program_hash:% = report.program_hash; "Program Hash");
+    info!(result_output_hash:% = report.output_hash; "Output Hash");
+    Ok(())
+}
+
+/// Verify `proof` end to end with the compiled verifier program at
+/// `program_path`, loaded into an in-process Solana runtime.
+///
+/// This runs the same upload → `PushTask` → `Execute` loop as `verify`, with
+/// the same transaction and compute unit limits, but without a validator. The
+/// program runs in the SBF VM, so compute budget, account size and stack frame
+/// limits are enforced exactly as on-chain.
+pub async fn run(program_path: &Path, proof: &StarkProof) -> Result<SimulationReport> {
+    let program_data = fs::read(program_path)?;
+    info!(size_in_bytes:% = program_data.len(); "Program binary");
+
+    let program_id = Pubkey::new_unique();
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        program_id,
+        Account {
+            lamports: Rent::default().minimum_balance(program_data.len()).max(1),
+            data: program_data,
+            owner: bpf_loader::id(),
+            executable: true,
+            rent_epoch: 0,
+        },
+    );
+
+    // Added frozen, as `deploy` leaves the constants account it creates
+    let constants = pedersen_constants(PEDERSEN_WINDOW_BITS);
+    let mut constants_data = vec![0u8; size_of::<ConstantsAccount>()];
+    constants_data[..CONSTANTS_HEADER.len()].copy_from_slice(&CONSTANTS_HEADER);
+    ConstantsAccount::split_mut(&mut constants_data)
+        .unwrap()
+        .0
+        .frozen = 1;
+    constants_data.extend_from_slice(&constants);
+    let constants_account = Pubkey::new_unique();
+    program_test.add_account(
+        constants_account,
+        Account {
+            lamports: Rent::default().minimum_balance(constants_data.len()),
+            data: constants_data,
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let stack_account = Keypair::new();
+    let space = size_of::<BidirectionalStackAccount>();
+    let create_account_ix = solana_system_interface::instruction::create_account(
+        &context.payer.pubkey(),
+        &stack_account.pubkey(),
+        Rent::default().minimum_balance(space),
+        space as u64,
+        &program_id,
+    );
+    let initialize_ix = initialize_instruction(
+        &program_id,
+        &stack_account.pubkey(),
+        &context.payer.pubkey(),
+    );
+    process_instructions(
+        &mut context,
+        &[create_account_ix, initialize_ix],
+        &[&stack_account],
+    )
+    .await?;
+    info!(public_key:% = stack_account.pubkey(), size_in_bytes:% = space; "Account created");
+
+    let proof_bytes = cast_struct_to_slice(proof);
+    let proof_offset = std::mem::offset_of!(BidirectionalStackAccount, proof);
+    let indices: [u64; 3] = [0, DEFAULT_CAPACITY as u64, 0];
+    let indices_offset = std::mem::offset_of!(BidirectionalStackAccount, front_index);
+
+    let account_data = get_account_data(&mut context, &stack_account.pubkey()).await?;
+    let mut upload_instructions = set_account_data_instructions(
+        &program_id,
+        &stack_account.pubkey(),
+        &context.payer.pubkey(),
+        proof_offset,
+        &account_data[proof_offset..proof_offset + proof_bytes.len()],
+        proof_bytes,
+        CHUNK_SIZE,
+    );
+    upload_instructions.extend(set_account_data_instructions(
+        &program_id,
+        &stack_account.pubkey(),
+        &context.payer.pubkey(),
+        indices_offset,
+        &account_data[indices_offset..indices_offset + size_of_val(&indices)],
+        cast_struct_to_slice(&indices),
+        CHUNK_SIZE,
+    ));
+    info!(instructions_number:% = upload_instructions.len(); "Uploading proof");
+    for instruction in upload_instructions {
+        process_with_limit(&mut context, instruction, SET_ACCOUNT_DATA_COMPUTE_UNITS).await?;
+    }
+
+    let push_task_ix = Instruction::new_with_borsh(
+        program_id,
+        &VerifierInstruction::PushTask(VerifyPublicInput::new().to_vec_with_type_tag()),
+        vec![
+            AccountMeta::new(stack_account.pubkey(), false),
+            AccountMeta::new_readonly(context.payer.pubkey(), true),
+        ],
+    );
+    process_instructions(&mut context, &[push_task_ix], &[]).await?;
+
+    let mut account_data = get_account_data(&mut context, &stack_account.pubkey()).await?;
+    let steps =
+        BidirectionalStackAccount::cast_mut(&mut account_data).simulate_with_constants(&constants);
+    info!(simulation_steps:% = steps; "Simulation steps");
+
+    let mut max_compute_units = 0;
+    let mut total_compute_units = 0;
+    let mut logs = Vec::new();
+    for i in 0..steps {
+        let execute_ix = Instruction::new_with_borsh(
+            program_id,
+            &VerifierInstruction::Execute(i as u32),
+            vec![
+                AccountMeta::new(stack_account.pubkey(), false),
+                AccountMeta::new_readonly(constants_account, false),
+            ],
+        );
+        let (compute_units, step_logs) =
+            process_with_limit(&mut context, execute_ix, EXECUTE_COMPUTE_UNITS)
+                .await
+                .map_err(|e| {
+                    ClientError::TransactionError(format!("Execute step {i} failed: {e}"))
+                })?;
+
+        trace!(step:% = i, compute_units:% = compute_units; "Step executed");
+        max_compute_units = max_compute_units.max(compute_units);
+        total_compute_units += compute_units;
+        logs.extend(step_logs);
+    }
+
+    let account_data = get_account_data(&mut context, &stack_account.pubkey()).await?;
+    let stack = BidirectionalStackAccount::cast(&account_data);
+    let (program_hash, output_hash) = verification_result(stack)?;
+
+    Ok(SimulationReport {
+        steps,
+        max_compute_units,
+        total_compute_units,
+        logs,
+        program_hash,
+        output_hash,
+    })
+}
+
+/// Process a single instruction with a compute unit limit and return the
+/// compute units it consumed along with the program logs
+async fn process_with_limit(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    limit: u32,
+) -> Result<(u64, Vec<String>)> {
+    let limit = ComputeBudgetInstruction::set_compute_unit_limit(limit);
+    process_instructions(context, &[instruction, limit], &[]).await
+}
+
+/// Process a transaction paid by the context payer and return the compute
+/// units it consumed along with the program logs, which are also included in
+/// the error on failure
+async fn process_instructions(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(u64, Vec<String>)> {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        context.last_blockhash,
+    );
+
+    let outcome = context
+        .banks_client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .map_err(|e| ClientError::TransactionError(e.to_string()))?;
+    let (logs, compute_units) = outcome
+        .metadata
+        .map(|metadata| (metadata.log_messages, metadata.compute_units_consumed))
+        .unwrap_or_default();
+
+    outcome
+        .result
+        .map_err(|e| ClientError::TransactionError(format!("{e}\n{}", logs.join("\n"))))?;
+
+    Ok((compute_units, logs))
+}
+
+async fn get_account_data(context: &mut ProgramTestContext, account: &Pubkey) -> Result<Vec<u8>> {
+    context
+        .banks_client
+        .get_account(*account)
+        .await
+        .map_err(|e| ClientError::TransactionError(e.to_string()))?
+        .map(|account| account.data)
+        .ok_or_else(|| ClientError::AccountError(format!("Account {account} not found")))
+}
@@ -8,17 +8,41 @@ use solana_program::{
 };
 use solana_rpc_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
+    account::Account,
     bpf_loader_upgradeable::UpgradeableLoaderState,
     commitment_config::CommitmentConfig,
     compute_budget::ComputeBudgetInstruction,
+    pubkey::Pubkey,
     signature::{Keypair, Signature, Signer},
-    transaction::Transaction,
+    transaction::{Transaction, TransactionError},
 };
 
+use stark::{
+    felt::Felt,
+    pedersen::{PedersenTables, PEDERSEN_TABLES_ID},
+    stark_proof::{reject::RejectReason, VerifyPublicInput},
+    swiftness::stark::types::StarkProof,
+};
 use std::{fs, path::Path, thread::sleep};
+use swiftness_proof_parser::{json_parser, transform::TransformTo, StarkProof as StarkProofParser};
+use utils::{constants::push_table, AccountCast, Executable, Reject};
+use verifier::{
+    instruction::VerifierInstruction,
+    state::{ConstantsAccount, StackAccount, VerificationState},
+};
 
-use crate::{ClientError, Config, Result};
+use crate::{
+    verify::{CHUNK_SIZE, EXECUTE_COMPUTE_UNITS, SET_ACCOUNT_DATA_COMPUTE_UNITS},
+    ClientError, Config, Result,
+};
 use log::{error, info, trace, warn};
+
+/// Keypair file of the constants account created by `deploy`
+pub const CONSTANTS_ACCOUNT_KEYPAIR: &str = "constants-account-keypair.json";
+/// Window width of the Pedersen tables `deploy` stores, which hash with a
+/// quarter of the lookups of the built-in 4-bit tables
+pub const PEDERSEN_WINDOW_BITS: usize = 8;
+
 /// Initialize the Solana RPC client and verify connection
 pub async fn initialize_client(config: &Config) -> Result<RpcClient> {
     info!(rpc_url:% = config.rpc_url;"Using RPC URL");
@@ -287,6 +311,278 @@ pub async fn setup_account(
     }
 }
 
+/// Create a verifier stack account owned by `program_id` with `space` bytes,
+/// funded for rent exemption
+///
+/// The size selects the layout the program uses for the account, usually
+/// `size_of::<BidirectionalStackAccount>()` or `size_of::<LargeStackAccount>()`.
+pub async fn create_stack_account(
+    client: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    stack_account: &Keypair,
+    space: usize,
+) -> Result<Signature> {
+    info!(public_key:% = stack_account.pubkey(); "Creating new account");
+
+    info!(size_in_bytes:% = space; "Account space");
+
+    let create_account_ix = solana_system_interface::instruction::create_account(
+        &payer.pubkey(),
+        &stack_account.pubkey(),
+        client.get_minimum_balance_for_rent_exemption(space).await?,
+        space as u64,
+        program_id,
+    );
+
+    // Initialized in the same transaction, so no one else can claim the account
+    let initialize_ix =
+        initialize_instruction(program_id, &stack_account.pubkey(), &payer.pubkey());
+
+    let create_account_tx = Transaction::new_signed_with_payer(
+        &[create_account_ix, initialize_ix],
+        Some(&payer.pubkey()),
+        &[payer, stack_account],
+        client.get_latest_blockhash().await?,
+    );
+
+    let signature = client
+        .send_and_confirm_transaction(&create_account_tx)
+        .await?;
+
+    Ok(signature)
+}
+
+/// `Initialize` instruction writing the account header of a new stack account
+/// and recording `authority` as its authority
+pub fn initialize_instruction(
+    program_id: &Pubkey,
+    stack_account: &Pubkey,
+    authority: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &VerifierInstruction::Initialize,
+        vec![
+            AccountMeta::new(*stack_account, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+/// Reset a stack account the payer is the authority of, so it can verify
+/// another proof
+///
+/// The proof region is kept, uploading the next proof then only sends the
+/// chunks that differ.
+pub async fn reset_stack_account(
+    client: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    stack_account: &Pubkey,
+) -> Result<Signature> {
+    info!(public_key:% = stack_account; "Resetting account");
+
+    let reset_data = borsh::to_vec(&VerifierInstruction::Reset(false))
+        .map_err(|e| ClientError::SerializationError(e.to_string()))?;
+    send_instruction(
+        client,
+        payer,
+        program_id,
+        vec![
+            AccountMeta::new(*stack_account, false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
+        ],
+        &reset_data,
+    )
+    .await
+}
+
+/// Constants region holding the Pedersen tables with `window_bits`-bit
+/// windows, what `deploy` stores in the constants account
+pub fn pedersen_constants(window_bits: usize) -> Vec<u8> {
+    let mut constants = Vec::new();
+    push_table(
+        &mut constants,
+        PEDERSEN_TABLES_ID,
+        &PedersenTables::generate_bytes(window_bits),
+    );
+    constants
+}
+
+/// Constants account created by `deploy` in the keypairs directory, if any
+pub fn deployed_constants_account(config: &Config) -> Option<Pubkey> {
+    read_keypair_file(config.keypairs_dir.join(CONSTANTS_ACCOUNT_KEYPAIR))
+        .ok()
+        .map(|keypair| keypair.pubkey())
+}
+
+/// Create a constants account owned by `program_id` holding `constants`,
+/// then freeze it so that `Execute` accepts it, `None` if it already was
+///
+/// The payer must be the upgrade authority of the program. The account is
+/// created and initialized in one transaction, the constants are uploaded
+/// like a proof, re-reading the account before every attempt. An account an
+/// interrupted call left unfrozen is resumed from what it holds on-chain.
+pub async fn create_constants_account(
+    client: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    constants_account: &Keypair,
+    constants: &[u8],
+    config: &Config,
+) -> Result<Option<Signature>> {
+    let account = constants_account.pubkey();
+    let existing = client
+        .get_account_with_commitment(&account, client.commitment())
+        .await?
+        .value;
+    match existing {
+        Some(existing) => {
+            if check_constants_account(&existing, program_id, &payer.pubkey(), constants)? {
+                return Ok(None);
+            }
+            info!(public_key:% = account; "Resuming constants account");
+        }
+        None => {
+            initialize_constants_account(client, payer, program_id, constants_account, constants)
+                .await?
+        }
+    }
+
+    let mut uploaded = false;
+    for attempt in 1..=config.transaction_retry_count {
+        let account_data = client
+            .get_account_data(&account)
+            .await
+            .map_err(ClientError::SolanaClientError)?;
+        let current = &account_data[size_of::<ConstantsAccount>()..];
+
+        let instructions: Vec<_> = changed_regions(current, constants, CHUNK_SIZE)
+            .into_iter()
+            .map(|(offset, region)| {
+                Instruction::new_with_borsh(
+                    *program_id,
+                    &VerifierInstruction::SetConstantsData(offset, region.to_vec()),
+                    vec![
+                        AccountMeta::new(account, false),
+                        AccountMeta::new_readonly(payer.pubkey(), true),
+                    ],
+                )
+            })
+            .collect();
+        if instructions.is_empty() {
+            uploaded = true;
+            break;
+        }
+
+        info!(attempt:% = attempt, instructions_number:% = instructions.len(); "Uploading constants");
+        send_and_confirm_with_limit(client, &instructions, payer, SET_ACCOUNT_DATA_COMPUTE_UNITS)
+            .await?;
+    }
+    if !uploaded {
+        return Err(ClientError::TransactionError(format!(
+            "Constants still differ after {} upload attempts",
+            config.transaction_retry_count
+        )));
+    }
+
+    let freeze_data = borsh::to_vec(&VerifierInstruction::FreezeConstants)
+        .map_err(|e| ClientError::SerializationError(e.to_string()))?;
+    send_instruction(
+        client,
+        payer,
+        program_id,
+        vec![
+            AccountMeta::new(account, false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
+        ],
+        &freeze_data,
+    )
+    .await
+    .map(Some)
+}
+
+/// Check that an existing account is a constants account of `program_id`
+/// written by `authority` and sized for `constants`, returning whether it is
+/// frozen
+///
+/// A frozen account must hold exactly `constants`.
+fn check_constants_account(
+    existing: &Account,
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    constants: &[u8],
+) -> Result<bool> {
+    if existing.owner != *program_id {
+        return Err(ClientError::AccountError(format!(
+            "Constants account is owned by {}, not {program_id}",
+            existing.owner
+        )));
+    }
+    let (fixed, current) = ConstantsAccount::split(&existing.data)
+        .ok_or_else(|| ClientError::AccountError("Not a constants account".to_string()))?;
+    if fixed.authority != *authority {
+        return Err(ClientError::AccountError(format!(
+            "Constants account authority is {}, not {authority}",
+            fixed.authority
+        )));
+    }
+    if current.len() != constants.len() {
+        return Err(ClientError::AccountError(format!(
+            "Constants account holds {} bytes of constants, {} expected",
+            current.len(),
+            constants.len()
+        )));
+    }
+    if fixed.is_frozen() && current != constants {
+        return Err(ClientError::AccountError(
+            "Constants account is frozen with other constants".to_string(),
+        ));
+    }
+    Ok(fixed.is_frozen())
+}
+
+/// Create a constants account and initialize it in one transaction
+async fn initialize_constants_account(
+    client: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    constants_account: &Keypair,
+    constants: &[u8],
+) -> Result<()> {
+    let space = size_of::<ConstantsAccount>() + constants.len();
+    info!(public_key:% = constants_account.pubkey(), size_in_bytes:% = space; "Creating constants account");
+
+    let create_account_ix = solana_system_interface::instruction::create_account(
+        &payer.pubkey(),
+        &constants_account.pubkey(),
+        client.get_minimum_balance_for_rent_exemption(space).await?,
+        space as u64,
+        program_id,
+    );
+    let initialize_ix = Instruction::new_with_borsh(
+        *program_id,
+        &VerifierInstruction::InitializeConstants,
+        vec![
+            AccountMeta::new(constants_account.pubkey(), false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
+            AccountMeta::new_readonly(
+                bpf_loader_upgradeable::get_program_data_address(program_id),
+                false,
+            ),
+        ],
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[create_account_ix, initialize_ix],
+        Some(&payer.pubkey()),
+        &[payer, constants_account],
+        client.get_latest_blockhash().await?,
+    );
+    client.send_and_confirm_transaction(&transaction).await?;
+    Ok(())
+}
+
 /// Send an instruction to a program
 pub async fn send_instruction(
     client: &RpcClient,
@@ -602,6 +898,22 @@ pub fn read_keypair_file<P: AsRef<Path>>(path: P) -> Result<Keypair> {
         .map_err(|e| ClientError::KeypairError(format!("Failed to create keypair from bytes: {e}")))
 }
 
+/// Read and parse a proof JSON file into the verifier's `StarkProof`, falling
+/// back to the bundled example proof when no path is given
+#[allow(clippy::result_large_err)]
+pub fn read_proof(path: Option<&Path>) -> Result<StarkProof> {
+    let input = match path {
+        Some(path) => fs::read_to_string(path).map_err(ClientError::IoError)?,
+        None => include_str!("../../example_proof/saya.json").to_string(),
+    };
+
+    let proof_json = serde_json::from_str::<json_parser::StarkProof>(&input)?;
+    let proof = StarkProofParser::try_from(proof_json)
+        .map_err(|e| ClientError::SerializationError(format!("Invalid proof: {e}")))?;
+
+    Ok(proof.transform_to())
+}
+
 /// Write a keypair to file with improved error handling
 #[allow(clippy::result_large_err)]
 pub fn write_keypair_file<P: AsRef<Path>>(keypair: &Keypair, path: P) -> Result<()> {
@@ -668,3 +980,333 @@ pub async fn send_and_confirm_with_limit(
     }
     Ok(())
 }
+
+/// Split `desired` into the regions that differ from the `current` account
+/// bytes and therefore have to be written.
+///
+/// Matching stretches (e.g. zeros in a freshly created account, or chunks that
+/// were already uploaded before an interruption) are skipped and the remaining
+/// bytes are coalesced into writes of at most `max_len` bytes. Every returned
+/// region starts and ends on a differing byte, so no write is spent on bytes
+/// that are already in place at its edges.
+pub fn changed_regions<'a>(
+    current: &[u8],
+    desired: &'a [u8],
+    max_len: usize,
+) -> Vec<(usize, &'a [u8])> {
+    assert_eq!(current.len(), desired.len());
+    let differs = |i: usize| current[i] != desired[i];
+
+    let mut regions = Vec::new();
+    let mut offset = 0;
+
+    while let Some(start) = (offset..desired.len()).find(|i| differs(*i)) {
+        let end = std::cmp::min(start + max_len, desired.len());
+        let len = (start..end)
+            .rev()
+            .find(|i| differs(*i))
+            .map_or(0, |last| last + 1 - start);
+
+        regions.push((start, &desired[start..start + len]));
+        offset = start + len;
+    }
+
+    regions
+}
+
+/// Build the `SetAccountData` instructions that turn the `current` bytes at
+/// `offset` of a verifier account into `desired`, eliding unchanged chunks
+pub fn set_account_data_instructions(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    authority: &Pubkey,
+    offset: usize,
+    current: &[u8],
+    desired: &[u8],
+    max_len: usize,
+) -> Vec<Instruction> {
+    changed_regions(current, desired, max_len)
+        .into_iter()
+        .map(|(start, region)| {
+            Instruction::new_with_borsh(
+                *program_id,
+                &VerifierInstruction::SetAccountData(offset + start, region.to_vec()),
+                vec![
+                    AccountMeta::new(*account, false),
+                    AccountMeta::new_readonly(*authority, true),
+                ],
+            )
+        })
+        .collect()
+}
+
+/// Upload `data` at `offset` into a verifier account.
+///
+/// The on-chain bytes are re-read before every attempt and only the regions
+/// that still differ are sent, so an interrupted upload resumes where it
+/// stopped and the function only returns once the account holds `data`.
+pub async fn upload_account_data(
+    client: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    account: &Pubkey,
+    offset: usize,
+    data: &[u8],
+    config: &Config,
+) -> Result<()> {
+    for attempt in 1..=config.transaction_retry_count {
+        let account_data = client
+            .get_account_data(account)
+            .await
+            .map_err(ClientError::SolanaClientError)?;
+        let current = account_data
+            .get(offset..offset + data.len())
+            .ok_or_else(|| {
+                ClientError::AccountError(format!(
+                    "Account {account} is too small to hold {} bytes at offset {offset}",
+                    data.len()
+                ))
+            })?;
+
+        let instructions = set_account_data_instructions(
+            program_id,
+            account,
+            &payer.pubkey(),
+            offset,
+            current,
+            data,
+            CHUNK_SIZE,
+        );
+        if instructions.is_empty() {
+            return Ok(());
+        }
+
+        info!(attempt:% = attempt, instructions_number:% = instructions.len(); "Uploading account data");
+        send_and_confirm_with_limit(client, &instructions, payer, SET_ACCOUNT_DATA_COMPUTE_UNITS)
+            .await?;
+    }
+
+    Err(ClientError::TransactionError(format!(
+        "Account data still differs after {} upload attempts",
+        config.transaction_retry_count
+    )))
+}
+
+/// Execute the remaining tasks of a verifier account one `Execute` step at a
+/// time, starting from its current on-chain `account_data`.
+///
+/// The scheduler is strictly sequential, so a step is only sent once the
+/// previous one is confirmed. Every `Execute` carries the account's executed
+/// steps counter, which the program checks, so a step can never be applied
+/// twice or out of order: when confirmation fails, the on-chain counter tells
+/// whether the step landed, and otherwise it is resent with a fresh blockhash.
+///
+/// The constants account, if any, is passed to every step and its constants
+/// are used in the replay too, so that tasks reading them, like
+/// `PedersenHash`, split their work the same way on both sides.
+///
+/// Returns the number of executed steps, or `ClientError::TransactionError`
+/// when a step fails on-chain, cannot land within the configured retries, or
+/// the counter moves in a way that does not match the local replay.
+pub async fn execute_steps<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+    client: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    account: &Pubkey,
+    constants_account: Option<&Pubkey>,
+    mut account_data: Vec<u8>,
+    config: &Config,
+) -> Result<u128> {
+    let constants_data = match constants_account {
+        Some(constants_account) => Some(
+            client
+                .get_account_data(constants_account)
+                .await
+                .map_err(ClientError::SolanaClientError)?,
+        ),
+        None => None,
+    };
+    let constants = match &constants_data {
+        Some(data) => ConstantsAccount::split(data)
+            .map(|(_, constants)| constants)
+            .ok_or_else(|| ClientError::AccountError("Not a constants account".to_string()))?,
+        None => &[],
+    };
+
+    let first_step = StackAccount::<CAPACITY, LENGTH_SIZE>::cast(&account_data).executed_steps;
+    let total_steps = StackAccount::<CAPACITY, LENGTH_SIZE>::cast_mut(&mut account_data)
+        .simulate_with_constants(constants);
+
+    let mut accounts = vec![AccountMeta::new(*account, false)];
+    accounts.extend(constants_account.map(|account| AccountMeta::new_readonly(*account, false)));
+
+    for step in first_step..first_step + total_steps as u64 {
+        let execute_ix = Instruction::new_with_borsh(
+            *program_id,
+            &VerifierInstruction::Execute(step as u32),
+            accounts.clone(),
+        );
+        let limit = ComputeBudgetInstruction::set_compute_unit_limit(EXECUTE_COMPUTE_UNITS);
+        let mut attempt = 0;
+
+        loop {
+            let blockhash = client
+                .get_latest_blockhash()
+                .await
+                .map_err(ClientError::SolanaClientError)?;
+            let transaction = Transaction::new_signed_with_payer(
+                &[execute_ix.clone(), limit.clone()],
+                Some(&payer.pubkey()),
+                &[payer],
+                blockhash,
+            );
+
+            let error = match client.send_and_confirm_transaction(&transaction).await {
+                Ok(signature) => {
+                    trace!(step:% = step, signature:% = signature; "Step confirmed");
+                    break;
+                }
+                Err(error) => error,
+            };
+
+            // A previous attempt may have landed after all, in which case this
+            // one was rejected by the counter check
+            let onchain_data = client
+                .get_account_data(account)
+                .await
+                .map_err(ClientError::SolanaClientError)?;
+            let executed_steps =
+                StackAccount::<CAPACITY, LENGTH_SIZE>::cast(&onchain_data).executed_steps;
+            if executed_steps == step + 1 {
+                trace!(step:% = step; "Step landed");
+                break;
+            }
+            if executed_steps != step {
+                return Err(ClientError::TransactionError(format!(
+                    "Account {account} executed {executed_steps} steps while sending step {step}"
+                )));
+            }
+
+            match error.get_transaction_error() {
+                None
+                | Some(TransactionError::BlockhashNotFound)
+                | Some(TransactionError::AlreadyProcessed) => {}
+                Some(error) => {
+                    return Err(ClientError::TransactionError(format!(
+                        "Execute step {step} failed: {error}"
+                    )));
+                }
+            }
+
+            attempt += 1;
+            if attempt >= config.transaction_retry_count {
+                return Err(ClientError::TransactionError(format!(
+                    "Execute step {step} not confirmed after {attempt} attempts: {error}"
+                )));
+            }
+            warn!(step:% = step, attempt:% = attempt, error:% = error; "Step not confirmed, retrying");
+            sleep(config.retry_sleep_duration());
+        }
+
+        let done = step + 1 - first_step;
+        if done % 20 == 0 || done == total_steps as u64 {
+            info!(step:% = done, total_steps:% = total_steps; "Steps confirmed");
+        }
+    }
+
+    Ok(total_steps)
+}
+
+/// Name of a rejection code, recorded by a proof task or by the scheduler
+pub fn reject_reason(code: u32) -> Option<String> {
+    RejectReason::from_code(code)
+        .map(|reason| format!("{reason:?}"))
+        .or_else(|| Reject::scheduler_reason(code).map(str::to_string))
+}
+
+/// Read the result of a finished verification: the program and output hashes
+/// recorded for a verified proof or a `VerifyPublicInput` root, or
+/// `ClientError::ProofRejected` with the reason and the type tag of the
+/// rejecting task.
+pub fn verification_result<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+    stack: &StackAccount<CAPACITY, LENGTH_SIZE>,
+) -> Result<(Felt, Felt)> {
+    let state = stack
+        .status
+        .state()
+        .map_err(|e| ClientError::AccountError(e.to_string()))?;
+
+    match state {
+        VerificationState::Verified => Ok((
+            Felt::from_bytes_be(&stack.status.program_hash),
+            Felt::from_bytes_be(&stack.status.output_hash),
+        )),
+        VerificationState::Rejected => {
+            let code = stack.status.error_code;
+            let reason = reject_reason(code).unwrap_or_else(|| format!("code {code}"));
+            Err(ClientError::ProofRejected(format!(
+                "{reason} in task {:#010x}",
+                stack.status.failed_task
+            )))
+        }
+        VerificationState::Completed if stack.status.root_task == VerifyPublicInput::TYPE_TAG => {
+            warn!("Only the public input was verified, the account is not a proof fact");
+            Ok((
+                Felt::from_bytes_be(&stack.status.program_hash),
+                Felt::from_bytes_be(&stack.status.output_hash),
+            ))
+        }
+        VerificationState::Completed => Err(ClientError::AccountError(
+            "The tasks completed without a verification root, no proof was verified".to_string(),
+        )),
+        state => Err(ClientError::AccountError(format!(
+            "Verification is not finished, state: {state:?}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::changed_regions;
+
+    #[test]
+    fn test_changed_regions_skips_zero_chunks() {
+        let current = vec![0u8; 4096];
+        let mut desired = vec![0u8; 4096];
+        desired[10] = 1;
+        desired[3000..3004].copy_from_slice(&[1, 0, 0, 2]);
+
+        let regions = changed_regions(&current, &desired, 900);
+        assert_eq!(regions, vec![(10, &[1u8][..]), (3000, &[1, 0, 0, 2][..])]);
+    }
+
+    #[test]
+    fn test_changed_regions_coalesces_up_to_max_len() {
+        let current = vec![0u8; 2000];
+        let desired = vec![7u8; 2000];
+
+        let regions = changed_regions(&current, &desired, 900);
+        assert_eq!(regions.len(), 3);
+        assert_eq!(regions[0].0, 0);
+        assert_eq!(regions[1].0, 900);
+        assert_eq!(regions[2].0, 1800);
+        assert_eq!(regions[2].1.len(), 200);
+    }
+
+    #[test]
+    fn test_changed_regions_skips_uploaded_bytes() {
+        let desired: Vec<u8> = (0..3000).map(|i| (i % 251) as u8 + 1).collect();
+        let mut current = desired.clone();
+        current[1000..1500].fill(0);
+
+        let regions = changed_regions(&current, &desired, 900);
+        assert_eq!(regions, vec![(1000, &desired[1000..1500])]);
+    }
+
+    #[test]
+    fn test_changed_regions_nothing_to_write() {
+        assert!(changed_regions(&[0u8; 1024], &[0u8; 1024], 900).is_empty());
+        assert!(changed_regions(&[3u8; 16], &[3u8; 16], 900).is_empty());
+        assert!(changed_regions(&[], &[], 900).is_empty());
+    }
+}
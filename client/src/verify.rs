@@ -1,26 +1,36 @@
 use crate::{
-    initialize_client, interact_with_program_instructions, send_and_confirm_with_limit,
-    setup_payer, ClientError,
+    create_stack_account, deployed_constants_account, execute_steps, initialize_client, read_proof,
+    reset_stack_account, send_instruction, setup_payer, upload_account_data, verification_result,
+    write_keypair_file, ClientError,
 };
-use crate::{read_keypair_file, Config, Result};
+use crate::{read_keypair_file, Config, Result, VerifyConfig};
 use log::info;
-use solana_sdk::{
-    instruction::{AccountMeta, Instruction},
-    signature::Keypair,
-    signer::Signer,
-};
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey, signature::Keypair, signer::Signer};
 use stark::{
-    felt::Felt, stark_proof::VerifyPublicInput, swiftness::stark::types::cast_struct_to_slice,
+    stark_proof::VerifyPublicInput,
+    swiftness::stark::types::{cast_struct_to_slice, StarkProof},
 };
-use swiftness_proof_parser::{json_parser, transform::TransformTo, StarkProof as StarkProofParser};
+use std::{fs, str::FromStr};
 use utils::AccountCast;
 use utils::BidirectionalStack;
 use utils::Executable;
-use verifier::{instruction::VerifierInstruction, state::BidirectionalStackAccount};
+use verifier::{
+    instruction::VerifierInstruction,
+    state::{
+        LargeStackAccount, StackAccount, VerificationState, DEFAULT_CAPACITY, DEFAULT_LENGTH_SIZE,
+        LARGE_CAPACITY, LARGE_LENGTH_SIZE,
+    },
+};
 
 pub const CHUNK_SIZE: usize = 900;
+/// Compute unit limit requested for every `SetAccountData` transaction
+pub const SET_ACCOUNT_DATA_COMPUTE_UNITS: u32 = 1_000;
+/// Compute unit limit requested for every `Execute` transaction
+pub const EXECUTE_COMPUTE_UNITS: u32 = 500_000;
 
-pub async fn verify(config: &Config) -> Result<()> {
+pub async fn verify(verify_config: &VerifyConfig) -> Result<()> {
+    let config = &verify_config.config;
     let client = initialize_client(config).await?;
     let payer = if let Some(ref payer_keypair) = config.payer_keypair {
         Keypair::from_base58_string(payer_keypair)
@@ -29,104 +39,197 @@ pub async fn verify(config: &Config) -> Result<()> {
     };
     info!(public_key:% = payer.pubkey(); "Using payer");
 
-    let program_keypair = read_keypair_file("keypairs/verifier-keypair.json").unwrap();
-    let program_id = program_keypair.pubkey();
+    let program_id = match verify_config.program_id {
+        Some(program_id) => program_id,
+        None => read_keypair_file(config.keypairs_dir.join("verifier-keypair.json"))?.pubkey(),
+    };
     info!(program_id:% = program_id; "Using program");
 
-    let stack_account = read_keypair_file("keypairs/stack-account-keypair.json").unwrap();
-    info!(public_key:% = stack_account.pubkey(); "Using stack account");
-
-    let time = std::time::Instant::now();
-    let input: [u64; 2] = [0, 65536];
-    let proof_bytes = cast_struct_to_slice(&input);
-    let new_offset = proof_bytes.len();
-    let stack_set_instructions = proof_bytes
-        .chunks(CHUNK_SIZE)
-        .enumerate()
-        .map(|(i, chunk)| {
-            Instruction::new_with_borsh(
-                program_id,
-                &VerifierInstruction::SetAccountData(i * CHUNK_SIZE, chunk.to_vec()),
-                vec![AccountMeta::new(stack_account.pubkey(), false)],
-            )
-        })
-        .collect::<Vec<_>>();
+    // Checked on the host first, so no fees are paid for a proof the program rejects
+    let proof_verifier = read_proof(verify_config.proof.as_deref())?;
+    let (program_hash, output_hash) = verifier::verify_proof(&proof_verifier)
+        .map_err(|e| ClientError::ProofRejected(e.to_string()))?;
+    info!(program_hash:% = program_hash, output_hash:% = output_hash; "Proof verified on the host");
 
-    let input = include_str!("../../example_proof/saya.json");
-    let proof_json = serde_json::from_str::<json_parser::StarkProof>(input).unwrap();
-    let proof = StarkProofParser::try_from(proof_json).unwrap();
+    let large_stack = match verify_config.account {
+        // An existing account keeps the layout it was created with
+        Some(account) => {
+            let account_data = client
+                .get_account_data(&account)
+                .await
+                .map_err(ClientError::SolanaClientError)?;
+            account_data.len() == size_of::<LargeStackAccount>()
+        }
+        None => verify_config.large_stack,
+    };
+    if large_stack {
+        verify_with_layout::<LARGE_CAPACITY, LARGE_LENGTH_SIZE>(
+            verify_config,
+            &client,
+            &payer,
+            &program_id,
+            &proof_verifier,
+        )
+        .await
+    } else {
+        verify_with_layout::<DEFAULT_CAPACITY, DEFAULT_LENGTH_SIZE>(
+            verify_config,
+            &client,
+            &payer,
+            &program_id,
+            &proof_verifier,
+        )
+        .await
+    }
+}
 
-    let proof_verifier = proof.transform_to();
-    let proof_bytes = cast_struct_to_slice(&proof_verifier);
+/// Verify `proof_verifier` on a stack account of the given layout, creating
+/// or taking one from the pool unless an account was passed
+async fn verify_with_layout<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+    verify_config: &VerifyConfig,
+    client: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    proof_verifier: &StarkProof,
+) -> Result<()> {
+    let config = &verify_config.config;
+    let pooled_account = if verify_config.account_pool {
+        take_pooled_account::<CAPACITY, LENGTH_SIZE>(client, payer, program_id, config).await?
+    } else {
+        None
+    };
 
-    info!(size_in_bytes:% = proof_bytes.len() / 1024; "Proof bytes in kb");
-    let mut proof_set_instructions = proof_bytes
-        .chunks(CHUNK_SIZE)
-        .enumerate()
-        .map(|(i, chunk)| {
-            Instruction::new_with_borsh(
+    let stack_account = match verify_config.account.or(pooled_account) {
+        Some(account) => account,
+        None => {
+            let keypair = Keypair::new();
+            write_keypair_file(
+                &keypair,
+                config
+                    .keypairs_dir
+                    .join(format!("stack-account-{}-keypair.json", keypair.pubkey())),
+            )?;
+            let signature = create_stack_account(
+                client,
+                payer,
                 program_id,
-                &VerifierInstruction::SetAccountData(new_offset + (i * CHUNK_SIZE), chunk.to_vec()),
-                vec![AccountMeta::new(stack_account.pubkey(), false)],
+                &keypair,
+                size_of::<StackAccount<CAPACITY, LENGTH_SIZE>>(),
             )
-        })
-        .collect::<Vec<_>>();
-    proof_set_instructions.extend(stack_set_instructions);
-    info!(instructions_number:% = proof_set_instructions.len(); "Instructions number");
-    send_and_confirm_with_limit(&client, &proof_set_instructions, &payer, 1_000).await?;
-    info!(time_in_seconds:% = time.elapsed().as_secs(); "Time taken to set proof");
-    let time2 = std::time::Instant::now();
-    let task = VerifyPublicInput::new();
+            .await?;
+            info!(signature:% = signature; "Account created successfully, pass --account to resume with it");
+            keypair.pubkey()
+        }
+    };
+    info!(public_key:% = stack_account; "Using stack account");
 
-    let verify_public_input_ix = Instruction::new_with_borsh(
-        program_id,
-        &VerifierInstruction::PushTask(task.to_vec_with_type_tag()),
-        vec![AccountMeta::new(stack_account.pubkey(), false)],
-    );
-
-    let signature = interact_with_program_instructions(
-        &client,
-        &payer,
-        &program_id,
-        &stack_account,
-        &[verify_public_input_ix],
-    )
-    .await?;
-    info!(signature:% = signature; "Verify public input");
+    let proof_bytes = cast_struct_to_slice(proof_verifier);
+    let proof_offset = std::mem::offset_of!(StackAccount<CAPACITY, LENGTH_SIZE>, proof);
+    info!(size_in_bytes:% = proof_bytes.len() / 1024; "Proof bytes in kb");
 
     let mut account_data = client
-        .get_account_data(&stack_account.pubkey())
+        .get_account_data(&stack_account)
         .await
         .map_err(ClientError::SolanaClientError)?;
-    let stack = BidirectionalStackAccount::cast_mut(&mut account_data);
-    let simulation_steps = stack.simulate();
+    if !StackAccount::<CAPACITY, LENGTH_SIZE>::is_valid(&account_data) {
+        return Err(ClientError::AccountError(format!(
+            "Stack account {stack_account} was not initialized by this program version"
+        )));
+    }
+    let stack = StackAccount::<CAPACITY, LENGTH_SIZE>::cast(&account_data);
 
-    info!(simulation_steps:% = simulation_steps; "Simulation steps");
+    // `Initialize` sets up empty stacks, so a pending account with empty
+    // stacks has no task pushed yet and its proof upload can be resumed
+    let started = stack.status.state != VerificationState::Pending as u32
+        || !(stack.is_empty_back() && stack.is_empty_front());
 
-    let mut instructions = vec![];
-    for i in 0..simulation_steps {
-        // Execute the task
-        let execute_ix = Instruction::new_with_borsh(
+    if !started {
+        let time = std::time::Instant::now();
+        // Only the chunks that are not on-chain yet are sent, which for a
+        // freshly created (zero-filled) account skips the all-zero ones
+        upload_account_data(
+            client,
+            payer,
             program_id,
-            &VerifierInstruction::Execute(i as u32),
-            vec![AccountMeta::new(stack_account.pubkey(), false)],
-        );
-        instructions.push(execute_ix);
+            &stack_account,
+            proof_offset,
+            proof_bytes,
+            config,
+        )
+        .await?;
+
+        let input: [u64; 3] = [0, CAPACITY as u64, 0];
+        upload_account_data(
+            client,
+            payer,
+            program_id,
+            &stack_account,
+            std::mem::offset_of!(StackAccount<CAPACITY, LENGTH_SIZE>, front_index),
+            cast_struct_to_slice(&input),
+            config,
+        )
+        .await?;
+        info!(time_in_seconds:% = time.elapsed().as_secs(); "Time taken to set proof");
+
+        let task = VerifyPublicInput::new();
+
+        let push_task_data =
+            borsh::to_vec(&VerifierInstruction::PushTask(task.to_vec_with_type_tag()))
+                .map_err(|e| ClientError::SerializationError(e.to_string()))?;
+
+        let signature = send_instruction(
+            client,
+            payer,
+            program_id,
+            vec![
+                AccountMeta::new(stack_account, false),
+                AccountMeta::new_readonly(payer.pubkey(), true),
+            ],
+            &push_task_data,
+        )
+        .await?;
+        info!(signature:% = signature; "Verify public input");
+
+        account_data = client
+            .get_account_data(&stack_account)
+            .await
+            .map_err(ClientError::SolanaClientError)?;
+    } else if account_data[proof_offset..proof_offset + proof_bytes.len()] != *proof_bytes {
+        return Err(ClientError::AccountError(format!(
+            "Stack account {stack_account} is verifying a different proof"
+        )));
+    } else {
+        let stack = StackAccount::<CAPACITY, LENGTH_SIZE>::cast(&account_data);
+        info!(front_index:% = stack.front_index, back_index:% = stack.back_index; "Resuming verification");
     }
 
-    send_and_confirm_with_limit(&client, &instructions, &payer, 500_000).await?;
+    let time2 = std::time::Instant::now();
 
+    // Steps are replayed locally from the current on-chain state, so only the
+    // ones that are still left get executed
+    let constants_account = verify_config
+        .constants_account
+        .or_else(|| deployed_constants_account(config));
+    let executed_steps = execute_steps::<CAPACITY, LENGTH_SIZE>(
+        client,
+        payer,
+        program_id,
+        &stack_account,
+        constants_account.as_ref(),
+        account_data,
+        config,
+    )
+    .await?;
+
+    info!(executed_steps:% = executed_steps; "Executed steps");
     info!(time_in_seconds:% = time2.elapsed().as_secs(); "Time taken to execute");
     // Read and display the result
-    let mut account_data = client
-        .get_account_data(&stack_account.pubkey())
+    let account_data = client
+        .get_account_data(&stack_account)
         .await
         .map_err(ClientError::SolanaClientError)?;
-    let stack = BidirectionalStackAccount::cast_mut(&mut account_data);
-    let result_program_hash = Felt::from_bytes_be_slice(stack.borrow_front());
-    stack.pop_front();
-    let result_output_hash = Felt::from_bytes_be_slice(stack.borrow_front());
-    stack.pop_front();
+    let stack = StackAccount::<CAPACITY, LENGTH_SIZE>::cast(&account_data);
+    let (result_program_hash, result_output_hash) = verification_result(stack)?;
     info!(result_program_hash:% = result_program_hash; "Program Hash");
     info!(result_output_hash:% = result_output_hash; "Output Hash");
     info!(front_index:% = stack.front_index; "Stack front index");
@@ -135,3 +238,57 @@ pub async fn verify(config: &Config) -> Result<()> {
     info!("Hash Public Inputs successfully executed on Solana!");
     Ok(())
 }
+
+/// Find a stack account of the given layout created earlier in the keypairs
+/// directory whose verification ended without a verified proof, and reset it
+/// for the next proof. Verified accounts are the on-chain facts of their
+/// proofs and are never reused.
+async fn take_pooled_account<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+    client: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    config: &Config,
+) -> Result<Option<Pubkey>> {
+    let mut candidates = Vec::new();
+    for entry in fs::read_dir(&config.keypairs_dir)? {
+        let file_name = entry?.file_name();
+        let Some(account) = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix("stack-account-"))
+            .and_then(|name| name.strip_suffix("-keypair.json"))
+            .and_then(|account| Pubkey::from_str(account).ok())
+        else {
+            continue;
+        };
+        candidates.push(account);
+    }
+    candidates.sort();
+
+    for account in candidates {
+        // Closed accounts no longer exist
+        let Ok(account_info) = client.get_account(&account).await else {
+            continue;
+        };
+        if account_info.owner != *program_id
+            || !StackAccount::<CAPACITY, LENGTH_SIZE>::is_valid(&account_info.data)
+        {
+            continue;
+        }
+
+        let stack = StackAccount::<CAPACITY, LENGTH_SIZE>::cast(&account_info.data);
+        let recyclable = matches!(
+            stack.status.state(),
+            Ok(VerificationState::Rejected | VerificationState::Completed)
+        );
+        if stack.authority != payer.pubkey() || !recyclable {
+            continue;
+        }
+
+        let signature = reset_stack_account(client, payer, program_id, &account).await?;
+        info!(public_key:% = account, signature:% = signature; "Reusing stack account from the pool");
+        return Ok(Some(account));
+    }
+
+    info!("No rejected or completed stack account in the pool");
+    Ok(None)
+}
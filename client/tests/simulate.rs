@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+
+use client::{read_proof, simulate};
+use stark::felt::Felt;
+
+/// Runs the full verification flow against the SBF build of the verifier,
+/// with `cargo build-sbf && cargo test -p client -- --ignored`.
+#[tokio::test]
+#[ignore = "requires the SBF build of the verifier, run `cargo build-sbf` first"]
+async fn simulate_example_proof() {
+    let program = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../target/deploy/verifier.so");
+    assert!(
+        program.exists(),
+        "{} not found, run `cargo build-sbf` first",
+        program.display()
+    );
+
+    let proof = read_proof(None).unwrap();
+    let report = simulate::run(&program, &proof).await.unwrap();
+
+    assert_eq!(
+        report.program_hash,
+        Felt::from_hex("0x5ab580b04e3532b6b18f81cfa654a05e29dd8e2352d88df1e765a84072db07").unwrap()
+    );
+    assert_eq!(
+        report.output_hash,
+        Felt::from_hex("0x3233b5615a8de5563f7d3ba086b8f260189ac47753a1c131d063ed3f6c24400")
+            .unwrap()
+    );
+}
@@ -0,0 +1,148 @@
+use crate::{frame::FrameStack, BidirectionalStack};
+
+/// Alignment of every table in a constants region, enough to cast tables of
+/// `u64` limbs such as field elements in place
+pub const TABLE_ALIGN: usize = 8;
+/// Size of the id and length stored before every table
+const TABLE_HEADER_SIZE: usize = 8;
+
+/// Table with the given id in a constants region, `None` if it holds none
+///
+/// A constants region is a sequence of tables, each stored as its id and byte
+/// length (little-endian `u32`s) followed by its bytes, padded to
+/// `TABLE_ALIGN`. Tables keep the alignment of the region, see `push_table`.
+pub fn find_table(constants: &[u8], id: u32) -> Option<&[u8]> {
+    let mut offset = 0;
+    while let Some(header) = constants.get(offset..offset + TABLE_HEADER_SIZE) {
+        let table_id = u32::from_le_bytes(header[0..4].try_into().unwrap());
+        let len = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+        let start = offset + TABLE_HEADER_SIZE;
+        let table = constants.get(start..start + len)?;
+        if table_id == id {
+            return Some(table);
+        }
+        offset = start + len.next_multiple_of(TABLE_ALIGN);
+    }
+    None
+}
+
+/// Append a table to a constants region
+pub fn push_table(constants: &mut Vec<u8>, id: u32, table: &[u8]) {
+    debug_assert_eq!(constants.len() % TABLE_ALIGN, 0);
+    constants.extend_from_slice(&id.to_le_bytes());
+    constants.extend_from_slice(&(table.len() as u32).to_le_bytes());
+    constants.extend_from_slice(table);
+    constants.resize(constants.len().next_multiple_of(TABLE_ALIGN), 0);
+}
+
+/// Stack that lends read-only constants to the tasks it runs, for stacks
+/// that cannot hold them themselves, like a stack account next to a
+/// constants account
+pub struct WithConstants<'a, S> {
+    stack: &'a mut S,
+    constants: &'a [u8],
+}
+
+impl<'a, S: BidirectionalStack> WithConstants<'a, S> {
+    pub fn new(stack: &'a mut S, constants: &'a [u8]) -> Self {
+        Self { stack, constants }
+    }
+}
+
+impl<S: BidirectionalStack> BidirectionalStack for WithConstants<'_, S> {
+    type Error = S::Error;
+
+    fn push_front(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.stack.push_front(data)
+    }
+
+    fn push_back(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.stack.push_back(data)
+    }
+
+    fn pop_front(&mut self) {
+        self.stack.pop_front()
+    }
+
+    fn pop_back(&mut self) {
+        self.stack.pop_back()
+    }
+
+    fn borrow_front(&self) -> &[u8] {
+        self.stack.borrow_front()
+    }
+
+    fn borrow_back(&self) -> &[u8] {
+        self.stack.borrow_back()
+    }
+
+    fn borrow_mut_front(&mut self) -> &mut [u8] {
+        self.stack.borrow_mut_front()
+    }
+
+    fn borrow_mut_back(&mut self) -> &mut [u8] {
+        self.stack.borrow_mut_back()
+    }
+
+    fn is_empty_front(&self) -> bool {
+        self.stack.is_empty_front()
+    }
+
+    fn is_empty_back(&self) -> bool {
+        self.stack.is_empty_back()
+    }
+
+    fn get_proof_bytes(&self) -> &[u8] {
+        self.stack.get_proof_bytes()
+    }
+
+    fn get_proof_bytes_mut(&mut self) -> &mut [u8] {
+        self.stack.get_proof_bytes_mut()
+    }
+
+    fn get_constants_bytes(&self) -> &[u8] {
+        self.constants
+    }
+}
+
+impl<S: FrameStack> FrameStack for WithConstants<'_, S> {
+    fn front_offset(&self, items: usize) -> Option<usize> {
+        self.stack.front_offset(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HeapStack;
+
+    #[test]
+    fn test_find_table() {
+        let mut constants = Vec::new();
+        push_table(&mut constants, 1, &[1, 2, 3]);
+        push_table(&mut constants, 2, &[]);
+        push_table(&mut constants, 3, &[4; 16]);
+        assert_eq!(constants.len() % TABLE_ALIGN, 0);
+
+        assert_eq!(find_table(&constants, 1), Some(&[1, 2, 3][..]));
+        assert_eq!(find_table(&constants, 2), Some(&[][..]));
+        assert_eq!(find_table(&constants, 3), Some(&[4; 16][..]));
+        assert_eq!(find_table(&constants, 4), None);
+        assert_eq!(find_table(&[], 1), None);
+
+        // A table cut off by the end of the region is not returned
+        assert_eq!(find_table(&constants[..30], 3), None);
+    }
+
+    #[test]
+    fn test_with_constants() {
+        let constants = [5u8; 8];
+        let mut stack = HeapStack::new();
+        assert!(stack.get_constants_bytes().is_empty());
+
+        let mut with_constants = WithConstants::new(&mut stack, &constants);
+        with_constants.push_front(&[1]).unwrap();
+        assert_eq!(with_constants.get_constants_bytes(), &constants);
+        assert_eq!(stack.borrow_front(), &[1]);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{BidirectionalStack, Frame, Reject};
+
+/// Size of the frame marker stored after every task on the back stack
+pub const FRAME_MARKER_SIZE: usize = 4;
+/// Frame marker of a task that did not run yet
+pub const FRAME_MARKER_UNSET: u32 = u32::MAX;
+
+/// Stack whose back stack holds every task followed by its frame marker, see
+/// `frame_entry`, so that `step` can enforce the frames of its tasks
+pub trait FrameStack: BidirectionalStack {
+    /// Position of the front stack below its top `items` items, `None` if it
+    /// holds fewer items
+    fn front_offset(&self, items: usize) -> Option<usize>;
+}
+
+/// Back stack entry of a task stored with its type tag
+///
+/// The task is followed by a frame marker, set when the task first runs to
+/// the front position below the inputs it takes from the data stack. Once
+/// the task finishes, exactly the outputs of its frame must be left above
+/// the marker, so its caller gets back the values it returned and nothing
+/// else.
+pub fn frame_entry(task: &[u8]) -> Vec<u8> {
+    let mut entry = Vec::with_capacity(task.len() + FRAME_MARKER_SIZE);
+    entry.extend_from_slice(task);
+    entry.extend_from_slice(&FRAME_MARKER_UNSET.to_be_bytes());
+    entry
+}
+
+/// Run the task on top of the back stack for one step, returning whether it
+/// finished, or the code it was rejected with
+///
+/// `execute` and `task_frame` are those of a task list, `register_tasks!`
+/// wraps this function with them as `step`. A task is rejected with
+/// `Reject::MISSING_FRAME_INPUTS` when it first runs without its inputs on
+/// the data stack, with `Reject::UNBALANCED_FRAME` when it finishes without
+/// leaving exactly its outputs, or with the code of the `Reject` it returns.
+/// A rejection is final, the caller records it and runs no other task.
+pub fn step<S: FrameStack>(
+    stack: &mut S,
+    execute: fn(&mut S) -> (Vec<Vec<u8>>, bool),
+    task_frame: fn(&[u8]) -> Option<Frame>,
+) -> Result<bool, u32> {
+    open_frame(stack, task_frame)?;
+    let (tasks, is_finished) = execute(stack);
+
+    if let Some(code) = tasks.first().and_then(|task| Reject::code_of(task)) {
+        return Err(code);
+    }
+
+    if is_finished {
+        // A task finishing with subtasks hands its frame over to them
+        if tasks.is_empty() {
+            check_frame(stack, task_frame)?;
+        }
+        stack.pop_back();
+    }
+
+    for task in tasks.iter().rev() {
+        let _ = stack.push_back(&frame_entry(task));
+    }
+    Ok(is_finished)
+}
+
+/// Frame marker of the task on top of the back stack
+fn frame_marker<S: BidirectionalStack>(stack: &mut S) -> &mut [u8] {
+    let entry = stack.borrow_mut_back();
+    let marker_start = entry.len() - FRAME_MARKER_SIZE;
+    &mut entry[marker_start..]
+}
+
+/// Set the frame marker of the task on top of the back stack if it is about
+/// to run for the first time, failing with `MISSING_FRAME_INPUTS` when the
+/// data stack does not hold its inputs
+fn open_frame<S: FrameStack>(
+    stack: &mut S,
+    task_frame: fn(&[u8]) -> Option<Frame>,
+) -> Result<(), u32> {
+    if frame_marker(stack) != FRAME_MARKER_UNSET.to_be_bytes() {
+        return Ok(());
+    }
+    let inputs = task_frame(stack.borrow_back()).map_or(0, |frame| frame.inputs);
+    let base = stack
+        .front_offset(inputs)
+        .ok_or(Reject::MISSING_FRAME_INPUTS)? as u32;
+    frame_marker(stack).copy_from_slice(&base.to_be_bytes());
+    Ok(())
+}
+
+/// Check that the finished task on top of the back stack left exactly its
+/// outputs above its frame marker, failing with `UNBALANCED_FRAME`
+fn check_frame<S: FrameStack>(
+    stack: &mut S,
+    task_frame: fn(&[u8]) -> Option<Frame>,
+) -> Result<(), u32> {
+    let Some(frame) = task_frame(stack.borrow_back()) else {
+        return Ok(());
+    };
+    let base = u32::from_be_bytes(frame_marker(stack).try_into().unwrap()) as usize;
+    if stack.front_offset(frame.outputs) != Some(base) {
+        return Err(Reject::UNBALANCED_FRAME);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{impl_type_identifiable, Executable, HeapStack, Scheduler, TypeIdentifiable};
+
+    /// Replaces its input with `outputs` copies of it
+    #[repr(C)]
+    #[derive(Debug)]
+    struct Spread {
+        outputs: usize,
+    }
+
+    impl_type_identifiable!(Spread);
+
+    impl Executable for Spread {
+        fn execute<T: BidirectionalStack>(&mut self, stack: &mut T) -> Vec<Vec<u8>> {
+            let input = stack.borrow_front().to_vec();
+            stack.pop_front();
+            for _ in 0..self.outputs {
+                stack.push_front(&input).unwrap();
+            }
+            vec![]
+        }
+
+        fn is_finished(&mut self) -> bool {
+            true
+        }
+
+        fn frame(&self) -> Option<Frame> {
+            Some(Frame {
+                inputs: 1,
+                outputs: 1,
+            })
+        }
+    }
+
+    /// Rejects with `code`
+    #[repr(C)]
+    #[derive(Debug)]
+    struct Refuse {
+        code: u32,
+    }
+
+    impl_type_identifiable!(Refuse);
+
+    impl Executable for Refuse {
+        fn execute<T: BidirectionalStack>(&mut self, _stack: &mut T) -> Vec<Vec<u8>> {
+            vec![Reject::new(self.code).to_vec_with_type_tag()]
+        }
+    }
+
+    // Only `step` of the generated functions is used
+    #[allow(dead_code)]
+    mod tasks {
+        use super::*;
+
+        crate::register_tasks!(Reject, Spread, Refuse);
+    }
+    use tasks::step;
+
+    #[test]
+    fn test_step() {
+        let mut stack = HeapStack::new();
+        stack.push_data(&[1]);
+        stack.push_task(Spread { outputs: 1 });
+        assert_eq!(step(&mut stack), Ok(true));
+        assert!(stack.is_empty_back());
+        assert_eq!(stack.borrow_front(), &[1]);
+
+        // Leaving an extra output breaks the frame
+        stack.push_task(Spread { outputs: 2 });
+        assert_eq!(step(&mut stack), Err(Reject::UNBALANCED_FRAME));
+
+        // A task needs its inputs on the data stack
+        let mut stack = HeapStack::new();
+        stack.push_task(Spread { outputs: 1 });
+        assert_eq!(step(&mut stack), Err(Reject::MISSING_FRAME_INPUTS));
+    }
+
+    #[test]
+    fn test_rejection() {
+        let mut stack = HeapStack::new();
+        stack.push_task(Refuse { code: 7 });
+        assert_eq!(step(&mut stack), Err(7));
+    }
+}
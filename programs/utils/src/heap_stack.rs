@@ -0,0 +1,174 @@
+use std::convert::Infallible;
+
+use crate::{
+    frame::{frame_entry, FrameStack},
+    BidirectionalStack, Executable, Scheduler,
+};
+
+/// Growable `BidirectionalStack` held in memory, for running task graphs off
+/// chain without the fixed capacity and size of a stack account
+///
+/// The proof is optional and borrowed. It is only copied when a task asks for
+/// mutable proof bytes.
+#[derive(Debug, Default)]
+pub struct HeapStack<'a> {
+    front: Vec<Vec<u8>>,
+    back: Vec<Vec<u8>>,
+    proof: Option<Proof<'a>>,
+}
+
+#[derive(Debug)]
+enum Proof<'a> {
+    Borrowed(&'a [u8]),
+    /// Copy of the proof bytes, held in words so that it is aligned for the
+    /// proof struct `get_proof_reference_mut` casts it to
+    Owned {
+        words: Vec<u64>,
+        len: usize,
+    },
+}
+
+impl<'a> HeapStack<'a> {
+    /// Empty stack without a proof
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Empty stack reading the proof from `proof`, usually the bytes of a
+    /// proof struct so that `get_proof_reference` can cast them back
+    pub fn with_proof(proof: &'a [u8]) -> Self {
+        Self {
+            proof: Some(Proof::Borrowed(proof)),
+            ..Self::default()
+        }
+    }
+
+    /// Number of items on the front stack
+    pub fn front_len(&self) -> usize {
+        self.front.len()
+    }
+
+    /// Number of tasks on the back stack
+    pub fn back_len(&self) -> usize {
+        self.back.len()
+    }
+}
+
+impl BidirectionalStack for HeapStack<'_> {
+    type Error = Infallible;
+
+    fn push_front(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.front.push(data.to_vec());
+        Ok(())
+    }
+
+    fn push_back(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.back.push(data.to_vec());
+        Ok(())
+    }
+
+    fn pop_front(&mut self) {
+        self.front.pop();
+    }
+
+    fn pop_back(&mut self) {
+        self.back.pop();
+    }
+
+    fn borrow_front(&self) -> &[u8] {
+        self.front.last().expect("front stack is empty")
+    }
+
+    fn borrow_back(&self) -> &[u8] {
+        self.back.last().expect("back stack is empty")
+    }
+
+    fn borrow_mut_front(&mut self) -> &mut [u8] {
+        self.front.last_mut().expect("front stack is empty")
+    }
+
+    fn borrow_mut_back(&mut self) -> &mut [u8] {
+        self.back.last_mut().expect("back stack is empty")
+    }
+
+    fn is_empty_front(&self) -> bool {
+        self.front.is_empty()
+    }
+
+    fn is_empty_back(&self) -> bool {
+        self.back.is_empty()
+    }
+
+    fn get_proof_bytes(&self) -> &[u8] {
+        match self.proof.as_ref().expect("no proof attached to the stack") {
+            Proof::Borrowed(bytes) => bytes,
+            Proof::Owned { words, len } => unsafe {
+                std::slice::from_raw_parts(words.as_ptr() as *const u8, *len)
+            },
+        }
+    }
+
+    fn get_proof_bytes_mut(&mut self) -> &mut [u8] {
+        let proof = self.proof.as_mut().expect("no proof attached to the stack");
+        if let Proof::Borrowed(bytes) = *proof {
+            let mut words = vec![0u64; bytes.len().div_ceil(8)];
+            let copy = unsafe {
+                std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, bytes.len())
+            };
+            copy.copy_from_slice(bytes);
+            *proof = Proof::Owned {
+                words,
+                len: bytes.len(),
+            };
+        }
+        match proof {
+            Proof::Owned { words, len } => unsafe {
+                std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, *len)
+            },
+            Proof::Borrowed(_) => unreachable!(),
+        }
+    }
+}
+
+impl FrameStack for HeapStack<'_> {
+    fn front_offset(&self, items: usize) -> Option<usize> {
+        self.front_len().checked_sub(items)
+    }
+}
+
+impl Scheduler for HeapStack<'_> {
+    fn push_task<T: Executable>(&mut self, task: T) {
+        self.push_back(&frame_entry(&task.to_vec_with_type_tag()))
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_items_and_proof() {
+        let proof = [7u8; 40];
+        let mut stack = HeapStack::with_proof(&proof);
+
+        // Items larger than the 65535 bytes an account item can hold
+        stack.push_front(&vec![1; 70_000]).unwrap();
+        stack.push_front(&[2, 3]).unwrap();
+        stack.push_back(&[4]).unwrap();
+        assert_eq!(stack.front_len(), 2);
+        assert_eq!(stack.borrow_front(), &[2, 3]);
+        stack.pop_front();
+        assert_eq!(stack.borrow_front().len(), 70_000);
+        assert_eq!(stack.borrow_back(), &[4]);
+        stack.pop_back();
+        assert!(stack.is_empty_back());
+
+        stack.get_proof_bytes_mut()[0] = 0;
+        assert_eq!(stack.get_proof_bytes()[..2], [0, 7]);
+        assert_eq!(stack.get_proof_bytes().len(), proof.len());
+        assert_eq!(proof[0], 7);
+        // The copy is aligned for proof structs of 8-byte words
+        assert_eq!(stack.get_proof_bytes_mut().as_ptr() as usize % 8, 0);
+    }
+}
@@ -1,16 +1,39 @@
 use std::fmt::Debug;
 
+pub mod constants;
+pub mod frame;
+pub mod heap_stack;
+pub mod reject;
+
+pub use constants::WithConstants;
+pub use heap_stack::HeapStack;
+pub use reject::Reject;
+
+#[doc(hidden)]
+pub use solana_program;
+
 /// Trait for safely casting between account data and Rust types
 pub trait AccountCast: Sized {
+    /// Bytes the account data must start with, identifying the layout it was
+    /// written with. Types without a header keep it empty.
+    const HEADER: &'static [u8] = &[];
+
+    /// Whether the slice has the size and header of Self
+    fn is_valid(slice: &[u8]) -> bool {
+        slice.len() == std::mem::size_of::<Self>() && slice.starts_with(Self::HEADER)
+    }
+
     /// Cast a slice to an immutable reference of Self
     fn cast(slice: &[u8]) -> &Self {
         assert_eq!(slice.len(), std::mem::size_of::<Self>());
+        assert!(slice.starts_with(Self::HEADER), "account header mismatch");
         unsafe { &*(slice.as_ptr() as *const Self) }
     }
 
     /// Cast a mutable slice to a mutable reference of Self
     fn cast_mut(slice: &mut [u8]) -> &mut Self {
         assert_eq!(slice.len(), std::mem::size_of::<Self>());
+        assert!(slice.starts_with(Self::HEADER), "account header mismatch");
         unsafe { &mut *(slice.as_mut_ptr() as *mut Self) }
     }
 }
@@ -46,6 +69,13 @@ pub trait BidirectionalStack {
     /// Get raw proof bytes - to be implemented by concrete types
     fn get_proof_bytes(&self) -> &[u8];
     fn get_proof_bytes_mut(&mut self) -> &mut [u8];
+
+    /// Read-only constants lent to the tasks, such as precomputed tables,
+    /// empty unless the stack runs with a constants region (see
+    /// `constants::find_table`)
+    fn get_constants_bytes(&self) -> &[u8] {
+        &[]
+    }
 }
 
 pub trait Scheduler: BidirectionalStack {
@@ -70,8 +100,17 @@ pub trait TypeIdentifiable {
 }
 
 // For automatic implementation of TypeIdentifiable
+//
+// The derived ID changes whenever the type is renamed or moved to another
+// module, so tasks that must stay readable from accounts created by an older
+// program build pin their ID with `impl_type_identifiable!(Type, 0x1234_5678)`
 #[macro_export]
 macro_rules! impl_type_identifiable {
+    ($type:ty, $type_id:expr) => {
+        impl TypeIdentifiable for $type {
+            const TYPE_ID: u32 = $type_id;
+        }
+    };
     ($type:ty) => {
         impl TypeIdentifiable for $type {
             // Generate a compile-time constant value based on type name using FNV-1a hash
@@ -101,24 +140,265 @@ macro_rules! impl_type_identifiable {
     };
 }
 
+/// Generate the `execute` dispatcher of a scheduler for an explicit list of tasks
+///
+/// The dispatcher matches the type tag of the task on top of the back stack
+/// against the `TYPE_TAG` of every listed task, runs it in place and returns
+/// the tasks it produced together with whether it finished. It is generic
+/// over the stack, so one list serves every stack layout. Tasks from any
+/// crate can be listed, so a task only runs on-chain once it is registered.
+///
+/// Schedulers run tasks with the generated `step`, which wraps `execute` with
+/// the frame checks of `frame::step`, so every stack runs the same list the
+/// same way.
+///
+/// Next to it, `TASK_TYPE_NAMES` maps every type tag to the path of its task,
+/// `task_frame` reads the `Frame` of a task and `debug_task` decodes a task
+/// with its type tag into its `Debug` output, for tooling that inspects stack
+/// accounts. Registered tasks must therefore implement `Debug`. Both return
+/// `None` for bytes that do not hold a valid task (see
+/// `Executable::is_valid_bytes`), which `execute` refuses to run.
+///
+/// Registering two tasks with the same `TYPE_TAG` fails to compile.
+///
+/// When `utils` is built with its `profile` feature, every task also logs
+/// `PROFILE_LOG_PREFIX`, its name and the compute units it consumed.
+///
+/// ```ignore
+/// utils::register_tasks!(
+///     arithmetic::add::Add,
+///     arithmetic::mul::Mul,
+/// );
+/// ```
+#[macro_export]
+macro_rules! register_tasks {
+    ($($task:ty),* $(,)?) => {
+        const _: () = assert!(
+            !$crate::has_duplicate_tags(&[$(<$task as $crate::Executable>::TYPE_TAG),*]),
+            "two registered tasks share a TYPE_TAG, pin a distinct one with impl_type_identifiable!"
+        );
+
+        /// Type tag and path of every registered task
+        pub const TASK_TYPE_NAMES: &[(u32, &str)] =
+            &[$((<$task as $crate::Executable>::TYPE_TAG, stringify!($task))),*];
+
+        /// Path of the registered task with the given type tag
+        pub fn task_type_name(type_tag: u32) -> Option<&'static str> {
+            TASK_TYPE_NAMES
+                .iter()
+                .find(|(tag, _)| *tag == type_tag)
+                .map(|(_, name)| *name)
+        }
+
+        /// Debug representation of a task stored with its type tag, as found
+        /// on the back stack, or `None` if it is not a registered task
+        pub fn debug_task(task: &[u8]) -> Option<String> {
+            let type_tag = u32::from_be_bytes(task.get(0..4)?.try_into().unwrap());
+            match type_tag {
+                $(
+                    <$task as $crate::Executable>::TYPE_TAG => {
+                        let bytes = task.get(4..(4 + std::mem::size_of::<$task>()))?;
+                        if !<$task as $crate::Executable>::is_valid_bytes(bytes) {
+                            return None;
+                        }
+                        // Tasks are plain data, but the bytes may be unaligned
+                        let task = std::mem::ManuallyDrop::new(unsafe {
+                            std::ptr::read_unaligned(bytes.as_ptr() as *const $task)
+                        });
+                        Some(format!("{:?}", *task))
+                    }
+                )*
+                _ => None,
+            }
+        }
+
+        /// Frame of a task stored with its type tag, `None` if the task does
+        /// not declare one or is not registered
+        pub fn task_frame(task: &[u8]) -> Option<$crate::Frame> {
+            let type_tag = u32::from_be_bytes(task.get(0..4)?.try_into().unwrap());
+            match type_tag {
+                $(
+                    <$task as $crate::Executable>::TYPE_TAG => {
+                        let bytes = task.get(4..(4 + std::mem::size_of::<$task>()))?;
+                        if !<$task as $crate::Executable>::is_valid_bytes(bytes) {
+                            return None;
+                        }
+                        let task = std::mem::ManuallyDrop::new(unsafe {
+                            std::ptr::read_unaligned(bytes.as_ptr() as *const $task)
+                        });
+                        $crate::Executable::frame(&*task)
+                    }
+                )*
+                _ => None,
+            }
+        }
+
+        /// Whether a task stored with its type tag reads constants, `false`
+        /// if it is not registered
+        pub fn task_reads_constants(task: &[u8]) -> bool {
+            let Some(type_tag) = task.get(0..4) else {
+                return false;
+            };
+            match u32::from_be_bytes(type_tag.try_into().unwrap()) {
+                $(
+                    <$task as $crate::Executable>::TYPE_TAG => {
+                        <$task as $crate::Executable>::READS_CONSTANTS
+                    }
+                )*
+                _ => false,
+            }
+        }
+
+        /// Run the task on top of the back stack for one step, enforcing its
+        /// frame, see `frame::step`
+        pub fn step<S: $crate::frame::FrameStack>(stack: &mut S) -> Result<bool, u32> {
+            $crate::frame::step(stack, execute::<S>, task_frame)
+        }
+
+        pub fn execute<S: $crate::BidirectionalStack>(stack: &mut S) -> (Vec<Vec<u8>>, bool) {
+            // Create a raw pointer to avoid multiple mutable borrow issues
+            let stack_ptr = stack as *mut S;
+
+            // Get the data from the back of the stack using unsafe
+            let data = unsafe { $crate::BidirectionalStack::borrow_mut_back(&mut *stack_ptr) };
+            if data.len() < 4 {
+                panic!("Data too short to contain type tag");
+            }
+
+            // Read the 32-bit type tag from the first 4 bytes
+            let type_tag = u32::from_be_bytes(data[0..4].try_into().unwrap());
+
+            match type_tag {
+                $(
+                    <$task as $crate::Executable>::TYPE_TAG => {
+                        // Execute the task using unsafe to get around borrow checker
+                        $crate::profile_task!($task, unsafe {
+                            let obj = <$task as $crate::Executable>::cast_mut(
+                                &mut data[4..(4 + std::mem::size_of::<$task>())],
+                            );
+                            let tasks = $crate::Executable::execute(obj, &mut *stack_ptr);
+                            (tasks, $crate::Executable::is_finished(obj))
+                        })
+                    }
+                )*
+                _ => panic!("Unknown type tag: {type_tag}"),
+            }
+        }
+    };
+}
+
+/// Prefix of the log line emitted after every task when built with the
+/// `profile` feature, followed by the task name and the compute units it
+/// consumed (including one `sol_remaining_compute_units` syscall)
+pub const PROFILE_LOG_PREFIX: &str = "profile";
+
+/// Evaluate the execution of a task, logging the compute units it consumed
+#[cfg(feature = "profile")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! profile_task {
+    ($task:ty, $execution:expr) => {{
+        let remaining_units = $crate::solana_program::compute_units::sol_remaining_compute_units();
+        let result = $execution;
+        $crate::solana_program::msg!(
+            "{} {} {}",
+            $crate::PROFILE_LOG_PREFIX,
+            $crate::task_name(stringify!($task)),
+            remaining_units.saturating_sub(
+                $crate::solana_program::compute_units::sol_remaining_compute_units()
+            )
+        );
+        result
+    }};
+}
+
+/// Evaluate the execution of a task
+#[cfg(not(feature = "profile"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! profile_task {
+    ($task:ty, $execution:expr) => {
+        $execution
+    };
+}
+
+/// Whether any two of the given type tags are equal, usable in const context
+#[doc(hidden)]
+pub const fn has_duplicate_tags(tags: &[u32]) -> bool {
+    let mut i = 0;
+    while i < tags.len() {
+        let mut j = i + 1;
+        while j < tags.len() {
+            if tags[i] == tags[j] {
+                return true;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Whether the byte at `offset` is a discriminant of a `#[repr(u8)]` enum
+/// whose variants run from zero to `last`, such as `true as u8` for a bool
+pub fn is_valid_discriminant(bytes: &[u8], offset: usize, last: u8) -> bool {
+    bytes
+        .get(offset)
+        .is_some_and(|&discriminant| discriminant <= last)
+}
+
+/// Short name of a task from its path, used as its key in profiling logs
+#[doc(hidden)]
+pub fn task_name(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path).trim()
+}
+
+/// Data stack items a task takes from its caller and leaves as its result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub inputs: usize,
+    pub outputs: usize,
+}
+
 pub trait Executable: Sized + TypeIdentifiable {
     /// The type tag is now automatically derived from TypeIdentifiable trait
     /// Using u32 instead of u8 for a much larger ID space
     const TYPE_TAG: u32 = Self::TYPE_ID;
+    /// Whether the task reads the constants of the stack, see
+    /// `BidirectionalStack::get_constants_bytes`. Schedulers refuse to run
+    /// such a task without constants.
+    const READS_CONSTANTS: bool = false;
+
     fn execute<T: BidirectionalStack>(&mut self, stack: &mut T) -> Vec<Vec<u8>>;
     fn is_finished(&mut self) -> bool {
         false
     }
 
+    /// Frame of the task, which schedulers enforce by rejecting a task that
+    /// breaks it. Tasks without a fixed contract with their caller return
+    /// `None`.
+    fn frame(&self) -> Option<Frame> {
+        None
+    }
+
+    /// Whether `bytes`, of the size of Self, hold a valid Self. Stack bytes
+    /// are only read as a task once this holds, so tasks holding enums or
+    /// bools check their discriminants with `is_valid_discriminant`.
+    fn is_valid_bytes(_bytes: &[u8]) -> bool {
+        true
+    }
+
     /// Cast a slice to an immutable reference of Self
     fn cast(slice: &[u8]) -> &Self {
         assert_eq!(slice.len(), std::mem::size_of::<Self>());
+        assert!(Self::is_valid_bytes(slice), "invalid task bytes");
         unsafe { &*(slice.as_ptr() as *const Self) }
     }
 
     /// Cast a mutable slice to a mutable reference of Self
     fn cast_mut(slice: &mut [u8]) -> &mut Self {
         assert_eq!(slice.len(), std::mem::size_of::<Self>());
+        assert!(Self::is_valid_bytes(slice), "invalid task bytes");
         unsafe { &mut *(slice.as_mut_ptr() as *mut Self) }
     }
 
@@ -147,3 +427,23 @@ pub trait Executable: Sized + TypeIdentifiable {
         vec
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_duplicate_tags() {
+        assert!(has_duplicate_tags(&[1, 2, 3, 2]));
+        assert!(!has_duplicate_tags(&[1, 2, 3]));
+        assert!(!has_duplicate_tags(&[]));
+    }
+
+    #[test]
+    fn test_is_valid_discriminant() {
+        let bytes = [0, 1, 2];
+        assert!(is_valid_discriminant(&bytes, 1, 1));
+        assert!(!is_valid_discriminant(&bytes, 2, 1));
+        assert!(!is_valid_discriminant(&bytes, 3, 2));
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{impl_type_identifiable, BidirectionalStack, Executable, TypeIdentifiable};
+
+/// Marker task returned by a task that rejects the proof.
+///
+/// It is never pushed: the scheduler recognises it among the returned tasks,
+/// records `code` together with the type tag of the rejecting task as the final
+/// status of the account and stops the verification.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reject {
+    pub code: u32,
+}
+
+impl_type_identifiable!(Reject);
+
+impl Reject {
+    /// Code recorded by the scheduler when a task starts without the inputs
+    /// of its frame on the data stack
+    pub const MISSING_FRAME_INPUTS: u32 = u32::MAX - 1;
+    /// Code recorded by the scheduler when a task finishes without leaving
+    /// exactly the outputs of its frame
+    pub const UNBALANCED_FRAME: u32 = u32::MAX;
+
+    pub fn new(code: u32) -> Self {
+        Self { code }
+    }
+
+    /// Name of a code recorded by the scheduler rather than by a task
+    pub fn scheduler_reason(code: u32) -> Option<&'static str> {
+        match code {
+            Self::MISSING_FRAME_INPUTS => Some("MissingFrameInputs"),
+            Self::UNBALANCED_FRAME => Some("UnbalancedFrame"),
+            _ => None,
+        }
+    }
+
+    /// Rejection code of a returned task, if it is a `Reject`
+    pub fn code_of(task: &[u8]) -> Option<u32> {
+        let (tag, data) = task.split_at_checked(4)?;
+        if u32::from_be_bytes(tag.try_into().ok()?) != Self::TYPE_TAG
+            || data.len() != std::mem::size_of::<Self>()
+        {
+            return None;
+        }
+        Some(u32::from_ne_bytes(data.try_into().ok()?))
+    }
+}
+
+impl Executable for Reject {
+    fn execute<T: BidirectionalStack>(&mut self, _stack: &mut T) -> Vec<Vec<u8>> {
+        vec![]
+    }
+
+    fn is_finished(&mut self) -> bool {
+        true
+    }
+}
@@ -1,318 +0,0 @@
-use std::collections::HashSet;
-use std::env;
-use std::fs;
-use std::io::Write;
-use std::path::{Path, PathBuf};
-
-fn main() {
-    println!("cargo:rerun-if-changed=src");
-
-    // Get the current crate name from Cargo.toml
-    let current_crate_name = env::var("CARGO_PKG_NAME").unwrap_or_else(|_| "unknown".to_string());
-
-    // Find the workspace root directory
-    let workspace_root = find_workspace_root().unwrap_or_else(|| PathBuf::from("."));
-
-    // Get the out directory for generated code
-    let out_dir = env::var_os("OUT_DIR").unwrap();
-    let out_filename = format!("{current_crate_name}_executable_dispatch.rs");
-    let out_path = Path::new(&out_dir).join(out_filename);
-
-    let mut types = HashSet::new();
-
-    // Find all crates in the workspace by reading the Cargo.toml
-    let workspace_members = get_workspace_members(&workspace_root);
-
-    // Search for Executable implementations in each workspace member
-    for member_path in workspace_members {
-        let src_dir = workspace_root.join(&member_path).join("src");
-        if src_dir.exists() {
-            let crate_name = member_path
-                .file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or("unknown");
-
-            // Use the actual crate name for the current crate, otherwise use the directory name
-            let effective_crate_name =
-                if member_path.to_string_lossy().contains(&current_crate_name) {
-                    "crate".to_string()
-                } else {
-                    crate_name.to_string()
-                };
-
-            visit_dir(&src_dir, &mut types, &effective_crate_name, &src_dir);
-        }
-    }
-
-    // Generate the dispatch code
-    let mut dispatch_code = String::new();
-
-    // Add header with imports
-    dispatch_code.push_str("// AUTO-GENERATED by build.rs\n");
-    dispatch_code.push_str("// Do not edit this file directly\n\n");
-
-    // Generate the execute function
-    dispatch_code
-        .push_str("pub fn execute(stack: &mut crate::state::BidirectionalStackAccount) -> (Vec<Vec<u8>>, bool) {\n");
-    dispatch_code.push_str("    // Create a raw pointer to avoid multiple mutable borrow issues\n");
-    dispatch_code
-        .push_str("    let stack_ptr = stack as *mut crate::state::BidirectionalStackAccount;\n");
-    dispatch_code.push_str("    \n");
-    dispatch_code.push_str("    // Get the data from the back of the stack using unsafe\n");
-    dispatch_code.push_str("    let data = unsafe { (*stack_ptr).borrow_mut_back() };\n");
-    dispatch_code.push_str("    let mut tasks = Vec::new();\n");
-    dispatch_code.push_str("    let is_finished;\n");
-
-    // We need to ensure we have enough data (at least 4 bytes for u32)
-    dispatch_code.push_str("    if data.len() < 4 {\n");
-    dispatch_code.push_str("        panic!(\"Data too short to contain type tag\");\n");
-    dispatch_code.push_str("    }\n");
-
-    // Read the 32-bit type tag from the first 4 bytes
-    dispatch_code
-        .push_str("    let type_tag = u32::from_be_bytes(data[0..4].try_into().unwrap());\n");
-
-    dispatch_code.push_str("    match type_tag {\n");
-
-    // Add a case for each type in all crates
-    for (type_name, crate_name) in &types {
-        dispatch_code.push_str(&format!("        // TYPE_TAG from {crate_name} crate\n"));
-        dispatch_code.push_str(&format!(
-            "        {crate_name}::{type_name}::TYPE_TAG => {{\n"
-        ));
-
-        dispatch_code.push_str(
-            "            // Execute the task using unsafe to get around borrow checker\n",
-        );
-        dispatch_code.push_str(&format!(
-                "            unsafe {{\n                let obj = {crate_name}::{type_name}::cast_mut(&mut data[4..(4 + std::mem::size_of::<{crate_name}::{type_name}>())]);\n                let returned_tasks = obj.execute(&mut *stack_ptr);\n                tasks.extend(returned_tasks);\n                is_finished = obj.is_finished();\n            }}\n"
-            ));
-        dispatch_code.push_str("        },\n");
-    }
-
-    // Add default case
-    dispatch_code.push_str("        _ => {\n");
-    dispatch_code.push_str("            panic!(\"Unknown type tag: {type_tag}\");\n");
-    dispatch_code.push_str("        }\n");
-    dispatch_code.push_str("    }\n");
-    dispatch_code.push_str("    (tasks, is_finished)\n");
-    dispatch_code.push_str("}\n");
-
-    // Write the generated code to a file
-    let mut file = fs::File::create(out_path).unwrap();
-    file.write_all(dispatch_code.as_bytes()).unwrap();
-
-    println!(
-        "cargo:warning=Auto-generated code for {} Executable types across workspace",
-        types.len()
-    );
-}
-
-// Find the workspace root directory by looking for Cargo.toml with [workspace]
-fn find_workspace_root() -> Option<PathBuf> {
-    let mut current_dir = env::current_dir().ok()?;
-
-    loop {
-        let cargo_toml_path = current_dir.join("Cargo.toml");
-
-        if cargo_toml_path.exists() {
-            if let Ok(content) = fs::read_to_string(&cargo_toml_path) {
-                if content.contains("[workspace]") {
-                    return Some(current_dir);
-                }
-            }
-        }
-
-        if !current_dir.pop() {
-            break;
-        }
-    }
-
-    None
-}
-
-// Get workspace members from Cargo.toml
-fn get_workspace_members(workspace_root: &Path) -> Vec<PathBuf> {
-    let cargo_toml_path = workspace_root.join("Cargo.toml");
-    let mut members = Vec::new();
-
-    if let Ok(content) = fs::read_to_string(&cargo_toml_path) {
-        // Parse the members section
-        if let Some(members_section) = content.split("[workspace]").nth(1) {
-            if let Some(members_list) = members_section.split("members").nth(1) {
-                if let Some(list) = members_list.split(']').next() {
-                    if let Some(list) = list.split('[').nth(1) {
-                        for member in list.split(',') {
-                            let member = member.trim().trim_matches('"');
-
-                            // Handle glob patterns like "programs/*"
-                            if member.contains('*') {
-                                let base_path = member.split('*').next().unwrap_or("");
-                                if let Ok(entries) = fs::read_dir(workspace_root.join(base_path)) {
-                                    // Using flatten() instead of manual if let
-                                    for path in entries.flatten().map(|entry| entry.path()) {
-                                        if path.is_dir() && path.join("Cargo.toml").exists() {
-                                            let rel_path =
-                                                pathdiff::diff_paths(&path, workspace_root)
-                                                    .unwrap_or_else(|| path.clone());
-                                            members.push(rel_path);
-                                        }
-                                    }
-                                }
-                            } else if !member.is_empty() {
-                                members.push(PathBuf::from(member));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    // Always include the current directory
-    members.push(PathBuf::from("."));
-
-    members
-}
-
-// Helper function to recursively visit directories and find Rust files
-fn visit_dir(dir: &Path, types: &mut HashSet<(String, String)>, crate_name: &str, base_dir: &Path) {
-    if dir.is_dir() {
-        if let Ok(entries) = fs::read_dir(dir) {
-            // Using flatten() instead of manual if let
-            for path in entries.flatten().map(|entry| entry.path()) {
-                if path.is_dir() {
-                    visit_dir(&path, types, crate_name, base_dir);
-                } else if let Some(extension) = path.extension() {
-                    if extension == "rs" {
-                        find_executable_types(&path, types, crate_name, base_dir);
-                    }
-                }
-            }
-        }
-    }
-}
-
-// Parse a Rust file to find types implementing the Executable trait
-fn find_executable_types(
-    file_path: &Path,
-    types: &mut HashSet<(String, String)>,
-    crate_name: &str,
-    base_dir: &Path,
-) {
-    let content = fs::read_to_string(file_path).unwrap_or_default();
-    let file_name = file_path.file_name().unwrap_or_default().to_string_lossy();
-
-    // Skip the traits.rs file since it defines the trait
-    if file_name == "traits.rs" {
-        return;
-    }
-
-    // Determine the module path based on the file path relative to the base directory
-    let module_path = if file_path.starts_with(base_dir) {
-        let rel_path = file_path.strip_prefix(base_dir).unwrap_or(file_path);
-        let components: Vec<String> = rel_path
-            .components()
-            .filter_map(|comp| {
-                let s = comp.as_os_str().to_string_lossy().to_string();
-                if s.ends_with(".rs") {
-                    let stem = Path::new(&s).file_stem()?.to_string_lossy().to_string();
-                    if stem == "mod" {
-                        // Skip mod.rs files when building the path
-                        None
-                    } else if stem != "lib" {
-                        // Include non-lib.rs files
-                        Some(stem)
-                    } else {
-                        // Skip lib.rs files
-                        None
-                    }
-                } else {
-                    Some(s)
-                }
-            })
-            .collect();
-
-        components.join("::")
-    } else {
-        // Fallback for files that can't be resolved relative to base_dir
-        file_path
-            .file_stem()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string()
-    };
-
-    // Detect all Executable implementations and their struct names
-    let mut struct_names = Vec::new();
-    let mut in_impl_block = false;
-    let mut current_struct = String::new();
-
-    // First pass: collect all struct names
-    for line in content.lines() {
-        let line = line.trim();
-
-        // Check for struct declarations (both public and private)
-        if line.starts_with("pub struct ") || line.starts_with("struct ") {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 3 {
-                let struct_name = parts[2].trim_matches(|c| c == '{' || c == ' ' || c == '\t');
-                struct_names.push(struct_name.to_string());
-            }
-        }
-    }
-
-    // Second pass: find Executable implementations
-    for line in content.lines() {
-        let line = line.trim();
-
-        // Start of an impl block for Executable
-        if line.contains("impl") && line.contains("Executable for") {
-            in_impl_block = true;
-
-            // Extract the struct name from the impl line
-            if let Some(struct_part) = line.split("for").nth(1) {
-                let struct_name = struct_part
-                    .trim()
-                    .trim_matches(|c| c == '{' || c == ' ' || c == '\t');
-                current_struct = struct_name.to_string();
-            }
-        } else if in_impl_block && line.contains("}") {
-            in_impl_block = false;
-
-            // If we found a valid implementation and the struct exists
-            if !current_struct.is_empty() && struct_names.contains(&current_struct) {
-                // Create the fully qualified path
-                let full_path = if module_path.is_empty() {
-                    current_struct.clone()
-                } else {
-                    format!("{module_path}::{current_struct}")
-                };
-
-                // Add the fully qualified name and crate
-                types.insert((full_path, crate_name.to_string()));
-                current_struct = String::new();
-            }
-        }
-    }
-
-    // Also check for direct pattern matches for backward compatibility
-    if content.contains("impl Executable for")
-        || content.contains("impl traits::Executable for")
-        || content.contains("impl crate::traits::Executable for")
-        || content.contains("impl dynamic::traits::Executable for")
-        || content.contains("impl utils::Executable for")
-    {
-        for struct_name in struct_names {
-            // Create the fully qualified path
-            let full_path = if module_path.is_empty() {
-                struct_name.clone()
-            } else {
-                format!("{module_path}::{struct_name}")
-            };
-
-            // Add the fully qualified name and crate
-            types.insert((full_path, crate_name.to_string()));
-        }
-    }
-}
@@ -1,6 +1,7 @@
 use std::io;
 use std::num::TryFromIntError;
 
+use solana_program::program_error::ProgramError;
 use thiserror::Error;
 
 /// Custom errors for the verifier program
@@ -54,4 +55,86 @@ pub enum VerifierError {
     /// General IO error.
     #[error(transparent)]
     Io(#[from] io::Error),
+
+    /// The `Execute` step does not match the executed steps counter.
+    #[error("Unexpected step - Execute step does not match the executed steps counter")]
+    UnexpectedStep,
+
+    /// The verification was already verified or rejected.
+    #[error("Verification finished - the account reached a final state")]
+    VerificationFinished,
+
+    /// The write overlaps a region only the program may modify.
+    #[error("Protected region - the account data region cannot be written")]
+    ProtectedRegion,
+
+    /// The account already has an authority.
+    #[error("Already initialized - the account already has an authority")]
+    AlreadyInitialized,
+
+    /// The instruction was not signed by the authority of the account.
+    #[error("Unauthorized - the account authority did not sign the instruction")]
+    Unauthorized,
+
+    /// The account holds a verified proof and must be kept.
+    #[error("Verified fact - an account with a verified proof cannot be closed or reset")]
+    VerifiedFact,
+
+    /// The account was not created with the layout of this program version.
+    #[error("Unsupported account version - the account layout does not match this program")]
+    UnsupportedAccountVersion,
+
+    /// The constants account is frozen and can no longer be written.
+    #[error("Frozen constants - the constants account can no longer be written")]
+    ConstantsFrozen,
+
+    /// The constants account is still writable and cannot be read by tasks.
+    #[error("Constants not frozen - the constants account must be frozen before use")]
+    ConstantsNotFrozen,
+
+    /// The first step ran, the inputs of the verification can no longer change.
+    #[error("Verification started - the account can only be written before the first step")]
+    VerificationStarted,
+
+    /// The verification already runs with another constants account.
+    #[error("Constants mismatch - the verification is pinned to another constants account")]
+    ConstantsMismatch,
+
+    /// The next task reads constants, but no constants account was passed.
+    #[error("Missing constants - the next task reads constants from a constants account")]
+    MissingConstants,
+}
+
+impl From<VerifierError> for ProgramError {
+    fn from(error: VerifierError) -> Self {
+        let code = match error {
+            VerifierError::InvalidOwner => 0,
+            VerifierError::SchedulerDeserializationError => 1,
+            VerifierError::SchedulerTaskPushError => 2,
+            VerifierError::SchedulerExecutionError => 3,
+            VerifierError::SchedulerDataPopError => 4,
+            VerifierError::SchedulerSerializationError => 5,
+            VerifierError::TryFromInt(_) => 6,
+            VerifierError::EmptyStack => 7,
+            VerifierError::StackCapacity => 8,
+            VerifierError::InvalidTaskLength => 9,
+            VerifierError::Execution(_) => 10,
+            VerifierError::Task(_) => 11,
+            VerifierError::InvalidData(_) => 12,
+            VerifierError::Io(_) => 13,
+            VerifierError::UnexpectedStep => 14,
+            VerifierError::VerificationFinished => 15,
+            VerifierError::ProtectedRegion => 16,
+            VerifierError::AlreadyInitialized => 17,
+            VerifierError::Unauthorized => 18,
+            VerifierError::VerifiedFact => 19,
+            VerifierError::UnsupportedAccountVersion => 20,
+            VerifierError::ConstantsFrozen => 21,
+            VerifierError::ConstantsNotFrozen => 22,
+            VerifierError::VerificationStarted => 23,
+            VerifierError::ConstantsMismatch => 24,
+            VerifierError::MissingConstants => 25,
+        };
+        ProgramError::Custom(code)
+    }
 }
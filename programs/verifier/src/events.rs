@@ -0,0 +1,126 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{log::sol_log_data, program::set_return_data};
+use stark::stark_proof::{
+    get_hash::GetHash, stark_commit::StarkCommit, stark_verify::StarkVerify,
+    validate_public_input::ValidatePublicInput, verify::Verify, HashPublicInputs,
+    VerifyPublicInput,
+};
+use utils::Executable;
+
+use crate::state::{StackAccount, VerificationState};
+
+/// Tasks whose completion is reported with a `StageCompleted` event
+pub const STAGE_TASKS: [u32; 7] = [
+    ValidatePublicInput::TYPE_TAG,
+    GetHash::TYPE_TAG,
+    StarkCommit::TYPE_TAG,
+    StarkVerify::TYPE_TAG,
+    VerifyPublicInput::TYPE_TAG,
+    HashPublicInputs::TYPE_TAG,
+    Verify::TYPE_TAG,
+];
+
+/// Machine-readable event logged with `sol_log_data` during `Execute`
+///
+/// Every event is a single borsh-encoded field, so it shows up in the
+/// transaction logs as one base64 value after `Program data:`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifierEvent {
+    /// The first step ran, `task` is the type tag of the root task
+    Started { task: u32 },
+    /// A stage task finished in the given step
+    StageCompleted { task: u32, step: u64 },
+    /// The proof was verified by a `Verify` root, hashes are big-endian
+    /// felts, see `VerificationStatus::program_hash`
+    Verified {
+        program_hash: [u8; 32],
+        output_hash: [u8; 32],
+    },
+    /// The proof was rejected, see `VerificationStatus`
+    Rejected { error_code: u32, failed_task: u32 },
+}
+
+impl VerifierEvent {
+    pub fn emit(&self) {
+        // Serializing a fixed size enum into a vector cannot fail
+        let data = borsh::to_vec(self).unwrap();
+        sol_log_data(&[&data]);
+    }
+}
+
+/// Status returned with `set_return_data` by every `Execute` instruction
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecuteResult {
+    pub executed_steps: u64,
+    pub state: u32,
+    pub error_code: u32,
+    pub failed_task: u32,
+}
+
+impl ExecuteResult {
+    pub fn new<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+        stack: &StackAccount<CAPACITY, LENGTH_SIZE>,
+    ) -> Self {
+        Self {
+            executed_steps: stack.executed_steps,
+            state: stack.status.state,
+            error_code: stack.status.error_code,
+            failed_task: stack.status.failed_task,
+        }
+    }
+
+    pub fn set_return_data(&self) {
+        let data = borsh::to_vec(self).unwrap();
+        set_return_data(&data);
+    }
+}
+
+/// Emit the events describing the step that was just executed
+///
+/// `task` is the type tag of the executed task and `completed` tells whether
+/// it finished in this step.
+pub fn emit_step_events<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+    stack: &StackAccount<CAPACITY, LENGTH_SIZE>,
+    task: u32,
+    completed: bool,
+) {
+    let step = stack.executed_steps - 1;
+    if step == 0 {
+        VerifierEvent::Started { task }.emit();
+    }
+
+    if completed && STAGE_TASKS.contains(&task) {
+        VerifierEvent::StageCompleted { task, step }.emit();
+    }
+
+    let status = stack.status;
+    match status.state() {
+        // Only a `Verify` root ends `Verified`, so the state alone is the fact
+        Ok(VerificationState::Verified) => VerifierEvent::Verified {
+            program_hash: status.program_hash,
+            output_hash: status.output_hash,
+        }
+        .emit(),
+        Ok(VerificationState::Rejected) => VerifierEvent::Rejected {
+            error_code: status.error_code,
+            failed_task: status.failed_task,
+        }
+        .emit(),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_roundtrip() {
+        let event = VerifierEvent::StageCompleted {
+            task: GetHash::TYPE_TAG,
+            step: 3,
+        };
+        let data = borsh::to_vec(&event).unwrap();
+        assert_eq!(VerifierEvent::try_from_slice(&data).unwrap(), event);
+    }
+}
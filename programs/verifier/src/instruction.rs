@@ -4,29 +4,103 @@ use borsh::{BorshDeserialize, BorshSerialize};
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub enum VerifierInstruction {
     /// Sets the data at the given offset in the verifier account
+    ///
+    /// Like `PushTask` and `PushData`, only allowed before the first
+    /// `Execute` step.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The verifier account
+    /// 1. `[signer]` The authority
     SetAccountData(usize, Vec<u8>),
 
     /// Pushes a task to the verifier account's bidirectional stack
     ///
+    /// The account ends up `Verified` only if the first step finds a single
+    /// `Verify` task that did not run yet, any other root ends up `Completed`.
+    /// A single `VerifyPublicInput` root still records its hashes.
+    ///
     /// Accounts expected:
     /// 0. `[writable]` The verifier account
+    /// 1. `[signer]` The authority
     PushTask(Vec<u8>),
 
     /// Pushes data to the verifier account's bidirectional stack
     ///
     /// Accounts expected:
     /// 0. `[writable]` The verifier account
+    /// 1. `[signer]` The authority
     PushData(Vec<u8>),
 
     /// Executes the next task in the verifier account's bidirectional stack
     ///
+    /// The step index must equal the account's executed steps counter, so
+    /// replayed or reordered transactions fail instead of advancing the
+    /// scheduler twice or out of order.
+    ///
+    /// Tasks that read constants, such as `PedersenHash`, take them from the
+    /// optional constants account, which must be frozen. A step running such
+    /// a task without it fails. The first step passing a constants account
+    /// pins it to the verification, later steps may only pass the same one.
+    ///
     /// Accounts expected:
     /// 0. `[writable]` The verifier account
+    /// 1. `[]` Optional, a constants account
     Execute(u32),
 
-    /// Closes the verifier account
+    /// Closes the verifier account, sending all its lamports to the recipient
+    ///
+    /// Accounts holding a verified proof cannot be closed. Accounts of the
+    /// program without a valid stack account header, such as ones of an older
+    /// layout version, are closed by the upgrade authority of the program.
     ///
     /// Accounts expected:
     /// 0. `[writable]` The verifier account
+    /// 1. `[signer]` The authority, or the upgrade authority for an account
+    ///    without a valid header
+    /// 2. `[writable]` The recipient of the lamports
+    /// 3. `[]` The program data account of the program, only for an account
+    ///    without a valid header
     Close,
+
+    /// Records the signer as the authority of a verifier account that has
+    /// none yet and empties its stacks
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The verifier account
+    /// 1. `[signer]` The authority
+    Initialize,
+
+    /// Empties the stacks and the status of the verifier account so it can
+    /// verify another proof, zeroing the proof too when the flag is set
+    ///
+    /// Like `Close`, refused for accounts holding a verified proof.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The verifier account
+    /// 1. `[signer]` The authority
+    Reset(bool),
+
+    /// Writes the header of a new constants account and records the signer,
+    /// which must be the upgrade authority of the program, as its authority
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The constants account, zero-filled and owned by the program
+    /// 1. `[signer]` The upgrade authority of the program
+    /// 2. `[]` The program data account of the program
+    InitializeConstants,
+
+    /// Sets the data at the given offset in the constants region of a
+    /// constants account that is not frozen yet
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The constants account
+    /// 1. `[signer]` The authority
+    SetConstantsData(usize, Vec<u8>),
+
+    /// Makes a constants account immutable, after which `Execute` accepts it
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The constants account
+    /// 1. `[signer]` The authority
+    FreezeConstants,
 }
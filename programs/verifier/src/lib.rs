@@ -1,7 +1,12 @@
 // Export modules
 pub mod entrypoint;
 pub mod error;
+pub mod events;
 pub mod instruction;
+pub mod native;
 pub mod processor;
 pub mod scheduler;
 pub mod state;
+
+pub use native::{verify_proof, VerifyError};
+pub use utils::PROFILE_LOG_PREFIX;
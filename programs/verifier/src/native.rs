@@ -0,0 +1,81 @@
+use std::fmt;
+
+use stark::{
+    felt::Felt,
+    stack::FeltStack,
+    stark_proof::{reject::RejectReason, verify::Verify},
+    swiftness::stark::types::{cast_struct_to_slice, StarkProof},
+};
+use utils::{BidirectionalStack, HeapStack, Scheduler};
+
+use crate::scheduler::{step, task_type_name};
+
+/// Reason `verify_proof` did not accept a proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    /// A task rejected the proof, `failed_task` is its type tag
+    Rejected { code: u32, failed_task: u32 },
+    /// The verification finished without leaving the program and output hashes
+    MissingResult,
+}
+
+impl VerifyError {
+    /// Reason of a rejection, if its code is known
+    pub fn reason(&self) -> Option<RejectReason> {
+        match self {
+            Self::Rejected { code, .. } => RejectReason::from_code(*code),
+            Self::MissingResult => None,
+        }
+    }
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rejected { code, failed_task } => {
+                write!(f, "proof rejected with code {code}")?;
+                if let Some(reason) = self.reason() {
+                    write!(f, " ({reason:?})")?;
+                }
+                match task_type_name(*failed_task) {
+                    Some(name) => write!(f, " by {name}"),
+                    None => write!(f, " by task {failed_task:#010x}"),
+                }
+            }
+            Self::MissingResult => write!(f, "verification left no program and output hash"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Verify a proof on the host, returning its program hash and output hash
+///
+/// This runs the same `Verify` task graph as the verifier program, step by
+/// step with the same task list and frame checks, so it accepts and rejects
+/// the same proofs, only without compute unit and account size limits.
+pub fn verify_proof(proof: &StarkProof) -> Result<(Felt, Felt), VerifyError> {
+    let mut stack = HeapStack::with_proof(cast_struct_to_slice(proof));
+    stack.push_task(Verify::new());
+
+    while !stack.is_empty_back() {
+        let type_tag = u32::from_be_bytes(stack.borrow_back()[0..4].try_into().unwrap());
+        if let Err(code) = step(&mut stack) {
+            return Err(VerifyError::Rejected {
+                code,
+                failed_task: type_tag,
+            });
+        }
+    }
+
+    // `HashPublicInputs` leaves the program hash on top of the output hash
+    let mut pop_hash = || {
+        if stack.is_empty_front() || stack.borrow_front().len() != 32 {
+            return Err(VerifyError::MissingResult);
+        }
+        Ok(stack.pop_felt())
+    };
+    let program_hash = pop_hash()?;
+    let output_hash = pop_hash()?;
+    Ok((program_hash, output_hash))
+}
@@ -1,54 +1,120 @@
 use borsh::BorshDeserialize;
+use solana_loader_v3_interface::{get_program_data_address, state::UpgradeableLoaderState};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
     program_error::ProgramError,
     pubkey::Pubkey,
-    rent::Rent,
-    sysvar::Sysvar,
 };
 use utils::{AccountCast, BidirectionalStack};
 
-use crate::{instruction::VerifierInstruction, state::BidirectionalStackAccount};
+use std::mem::offset_of;
+
+use crate::{
+    error::VerifierError,
+    events::{emit_step_events, ExecuteResult},
+    instruction::VerifierInstruction,
+    scheduler::task_reads_constants,
+    state::{
+        account_version, ConstantsAccount, LargeStackAccount, StackAccount, VerificationState,
+        ACCOUNT_HEADER, ACCOUNT_VERSION, CONSTANTS_HEADER, CONSTANTS_VERSION, DEFAULT_CAPACITY,
+        DEFAULT_LENGTH_SIZE, LARGE_CAPACITY, LARGE_LENGTH_SIZE,
+    },
+};
 
 /// Program state handler
 pub struct Processor;
 
 impl Processor {
     /// Process the initialize instruction
-    pub fn process_initialize(accounts: &[AccountInfo]) -> ProgramResult {
+    pub fn process_initialize<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
         msg!("Processing Initialize instruction");
 
-        // Get the account to initialize
+        // Get the account to initialize and its future authority
         let accounts_iter = &mut accounts.iter();
         let account = next_account_info(accounts_iter)?;
+        let authority = next_account_info(accounts_iter)?;
+        if !authority.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
 
-        // Initialize the bidirectional stack
         let mut data = account.try_borrow_mut_data()?;
-        let stack_account = BidirectionalStackAccount::cast_mut(*data);
+        if data.len() != size_of::<StackAccount<CAPACITY, LENGTH_SIZE>>() {
+            msg!(
+                "Account size {} does not match the stack account layout",
+                data.len()
+            );
+            return Err(VerifierError::UnsupportedAccountVersion.into());
+        }
+        match account_version(&data) {
+            // A new account, the header makes it a stack account
+            None => data[..ACCOUNT_HEADER.len()].copy_from_slice(&ACCOUNT_HEADER),
+            Some(ACCOUNT_VERSION) => {}
+            Some(version) => {
+                msg!("Account version {} is not supported", version);
+                return Err(VerifierError::UnsupportedAccountVersion.into());
+            }
+        }
+
+        let stack_account = StackAccount::<CAPACITY, LENGTH_SIZE>::cast_mut(*data);
+        if stack_account.has_authority() {
+            msg!("Account already has an authority");
+            return Err(VerifierError::AlreadyInitialized.into());
+        }
 
-        // Set to default values - front_index to 0, back_index to CAPACITY
-        *stack_account = BidirectionalStackAccount::default();
+        // Front index to 0, back index to the capacity of the layout
+        stack_account.reset(false);
+        stack_account.authority = *authority.key;
         msg!("Account initialized successfully");
 
         Ok(())
     }
 
+    /// Process the reset instruction
+    pub fn process_reset<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+        accounts: &[AccountInfo],
+        clear_proof: bool,
+    ) -> ProgramResult {
+        msg!("Processing Reset instruction");
+
+        let accounts_iter = &mut accounts.iter();
+        let account = next_account_info(accounts_iter)?;
+        let authority = next_account_info(accounts_iter)?;
+
+        let mut data = account.try_borrow_mut_data()?;
+        let stack_account = load_stack_account::<CAPACITY, LENGTH_SIZE>(*data)?;
+        check_authority(stack_account, authority)?;
+        check_not_verified(stack_account)?;
+
+        stack_account.reset(clear_proof);
+        msg!("Account reset successfully");
+
+        Ok(())
+    }
+
     /// Process the push task instruction
-    pub fn process_push_task(accounts: &[AccountInfo], task_data: Vec<u8>) -> ProgramResult {
+    pub fn process_push_task<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+        accounts: &[AccountInfo],
+        task_data: Vec<u8>,
+    ) -> ProgramResult {
         msg!("Processing PushTask instruction");
 
-        // Get the account to push task to
+        // Get the account to push task to and its authority
         let accounts_iter: &mut std::slice::Iter<'_, AccountInfo<'_>> = &mut accounts.iter();
         let account = next_account_info(accounts_iter)?;
+        let authority = next_account_info(accounts_iter)?;
 
         // Push the task to the bidirectional stack
         let mut data = account.try_borrow_mut_data()?;
-        let stack_account = BidirectionalStackAccount::cast_mut(*data);
+        let stack_account = load_stack_account::<CAPACITY, LENGTH_SIZE>(*data)?;
+        check_authority(stack_account, authority)?;
+        check_pending(stack_account)?;
 
         // Push the task data to the back of the stack
-        stack_account.push_back(&task_data).map_err(|e| {
+        stack_account.schedule(&task_data).map_err(|e| {
             msg!("Error pushing task: {:?}", e);
             ProgramError::InvalidInstructionData
         })?;
@@ -58,16 +124,22 @@ impl Processor {
     }
 
     /// Process the push data instruction
-    pub fn process_push_data(accounts: &[AccountInfo], data_payload: Vec<u8>) -> ProgramResult {
+    pub fn process_push_data<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+        accounts: &[AccountInfo],
+        data_payload: Vec<u8>,
+    ) -> ProgramResult {
         msg!("Processing PushData instruction");
 
-        // Get the account to push data to
+        // Get the account to push data to and its authority
         let accounts_iter = &mut accounts.iter();
         let account = next_account_info(accounts_iter)?;
+        let authority = next_account_info(accounts_iter)?;
 
         // Push the data to the bidirectional stack
         let mut data = account.try_borrow_mut_data()?;
-        let stack_account = BidirectionalStackAccount::cast_mut(*data);
+        let stack_account = load_stack_account::<CAPACITY, LENGTH_SIZE>(*data)?;
+        check_authority(stack_account, authority)?;
+        check_pending(stack_account)?;
 
         // Push the data to the front of the stack
         stack_account.push_front(&data_payload).map_err(|e| {
@@ -80,69 +152,432 @@ impl Processor {
     }
 
     /// Process the execute instruction
-    pub fn process_execute(accounts: &[AccountInfo], nonce: u32) -> ProgramResult {
-        msg!("Processing Execute instruction, nonce: {}", nonce);
+    pub fn process_execute<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        step: u32,
+    ) -> ProgramResult {
+        msg!("Processing Execute instruction, step: {}", step);
 
-        // Get the account to execute task from
+        // Get the account to execute task from and the optional constants
         let accounts_iter = &mut accounts.iter();
         let account = next_account_info(accounts_iter)?;
+        let constants_data = match accounts_iter.next() {
+            Some(constants_account) => {
+                Some((constants_account, constants_account.try_borrow_data()?))
+            }
+            None => None,
+        };
+        let constants = match &constants_data {
+            Some((constants_account, data)) => {
+                load_frozen_constants(program_id, constants_account, data)?
+            }
+            None => &[],
+        };
 
         // Execute the next task in the stack
         let mut data = account.try_borrow_mut_data()?;
-        let stack_account = BidirectionalStackAccount::cast_mut(*data);
+        let stack_account = load_stack_account::<CAPACITY, LENGTH_SIZE>(*data)?;
+        check_not_final(stack_account)?;
+
+        // Only the next step in order may run, replays and skipped steps fail
+        if u64::from(step) != stack_account.executed_steps {
+            msg!(
+                "Unexpected step {}, expected step {}",
+                step,
+                stack_account.executed_steps
+            );
+            return Err(VerifierError::UnexpectedStep.into());
+        }
+
+        // Every step reads the constants of the account the first one passed
+        if let Some((constants_account, _)) = &constants_data {
+            pin_constants_account(stack_account, constants_account.key)?;
+        }
+        if stack_account.is_empty_back() {
+            msg!("No task left to execute");
+            return Err(VerifierError::EmptyStack.into());
+        }
+        if constants.is_empty() && task_reads_constants(stack_account.borrow_back()) {
+            msg!("The next task reads constants, pass the constants account");
+            return Err(VerifierError::MissingConstants.into());
+        }
 
         // Execute the task
-        stack_account.execute();
+        let (task, completed) = stack_account.execute_with_constants(constants)?;
         msg!("Task executed successfully");
 
+        emit_step_events(stack_account, task, completed);
+        ExecuteResult::new(stack_account).set_return_data();
+
+        let status = stack_account.status;
+        if status.state()? == VerificationState::Rejected {
+            msg!(
+                "Proof rejected with code {} by task {:#010x}",
+                status.error_code,
+                status.failed_task
+            );
+        }
+
         Ok(())
     }
 
-    pub fn process_set_account_data(
+    pub fn process_set_account_data<const CAPACITY: usize, const LENGTH_SIZE: usize>(
         accounts: &[AccountInfo],
         offset: usize,
         data: Vec<u8>,
     ) -> ProgramResult {
         msg!("Processing SetProof instruction");
-        // Get the account to set proof to
+        // Get the account to set proof to and its authority
         let accounts_iter = &mut accounts.iter();
         let account = next_account_info(accounts_iter)?;
+        let authority = next_account_info(accounts_iter)?;
         let account_data = &mut account.try_borrow_mut_data()?;
+        let stack_account = load_stack_account::<CAPACITY, LENGTH_SIZE>(&mut account_data[..])?;
+        check_authority(stack_account, authority)?;
+        check_pending(stack_account)?;
+
+        // The header, the status, the authority and the pinned constants
+        // account are only ever written by the program itself
+        let header_end = size_of_val(&ACCOUNT_HEADER);
+        let status_start = offset_of!(StackAccount<CAPACITY, LENGTH_SIZE>, status);
+        let constants_account_end = offset_of!(StackAccount<CAPACITY, LENGTH_SIZE>, constants_account)
+            + size_of::<Pubkey>();
+        let overlaps =
+            |start: usize, end: usize| offset < end && start < offset.saturating_add(data.len());
+        if overlaps(0, header_end) || overlaps(status_start, constants_account_end) {
+            msg!("Writing the header, status, authority or constants account is not allowed");
+            return Err(VerifierError::ProtectedRegion.into());
+        }
 
-        account_data[offset..offset + data.len()].copy_from_slice(&data);
+        let region = offset
+            .checked_add(data.len())
+            .and_then(|end| account_data.get_mut(offset..end))
+            .ok_or(ProgramError::AccountDataTooSmall)?;
+        region.copy_from_slice(&data);
         msg!("Proof part set successfully");
         Ok(())
     }
 
-    // src: https://github.com/solana-developers/program-examples/blob/main/basics/close-account/native/program/src/instructions/close_user.rs
-    pub fn close(accounts: &[AccountInfo]) -> ProgramResult {
+    /// Process the initialize constants instruction
+    pub fn process_initialize_constants(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        msg!("Processing InitializeConstants instruction");
+
         let accounts_iter = &mut accounts.iter();
-        let target_account = next_account_info(accounts_iter)?;
-        let payer = next_account_info(accounts_iter)?;
-        let system_program = next_account_info(accounts_iter)?;
+        let account = next_account_info(accounts_iter)?;
+        let authority = next_account_info(accounts_iter)?;
+        let program_data = next_account_info(accounts_iter)?;
+        check_upgrade_authority(program_id, program_data, authority)?;
+
+        // Any zero-filled account of the program could become one, stack
+        // accounts included, the header keeps them apart
+        let mut data = account.try_borrow_mut_data()?;
+        let header_end = size_of::<ConstantsAccount>();
+        if data.len() < header_end || data[..header_end].iter().any(|byte| *byte != 0) {
+            msg!("Constants accounts must be created empty and zero-filled");
+            return Err(VerifierError::AlreadyInitialized.into());
+        }
+
+        data[..CONSTANTS_HEADER.len()].copy_from_slice(&CONSTANTS_HEADER);
+        let (constants_account, _) = ConstantsAccount::split_mut(&mut data[..]).unwrap();
+        constants_account.authority = *authority.key;
+        msg!("Constants account initialized successfully");
+
+        Ok(())
+    }
+
+    /// Process the set constants data instruction
+    pub fn process_set_constants_data(
+        accounts: &[AccountInfo],
+        offset: usize,
+        data: Vec<u8>,
+    ) -> ProgramResult {
+        msg!("Processing SetConstantsData instruction");
+
+        let accounts_iter = &mut accounts.iter();
+        let account = next_account_info(accounts_iter)?;
+        let authority = next_account_info(accounts_iter)?;
+
+        let mut account_data = account.try_borrow_mut_data()?;
+        let (constants_account, constants) = load_writable_constants(&mut account_data[..])?;
+        check_constants_authority(constants_account, authority)?;
+
+        let region = offset
+            .checked_add(data.len())
+            .and_then(|end| constants.get_mut(offset..end))
+            .ok_or(ProgramError::AccountDataTooSmall)?;
+        region.copy_from_slice(&data);
+
+        Ok(())
+    }
+
+    /// Process the freeze constants instruction
+    pub fn process_freeze_constants(accounts: &[AccountInfo]) -> ProgramResult {
+        msg!("Processing FreezeConstants instruction");
+
+        let accounts_iter = &mut accounts.iter();
+        let account = next_account_info(accounts_iter)?;
+        let authority = next_account_info(accounts_iter)?;
 
-        let account_span = 0usize;
-        let lamports_required = (Rent::get()?).minimum_balance(account_span);
+        let mut account_data = account.try_borrow_mut_data()?;
+        let (constants_account, _) = load_writable_constants(&mut account_data[..])?;
+        check_constants_authority(constants_account, authority)?;
 
-        let diff = target_account.lamports() - lamports_required;
+        constants_account.frozen = 1;
+        msg!("Constants account frozen");
 
-        // Send the rent back to the payer
-        **target_account.lamports.borrow_mut() -= diff;
-        **payer.lamports.borrow_mut() += diff;
+        Ok(())
+    }
+
+    /// Process the close instruction
+    ///
+    /// All lamports go to the recipient and the data is zeroed before the
+    /// account is handed back to the System Program, so nothing of it is left
+    /// for the runtime to keep alive.
+    pub fn close<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        msg!("Processing Close instruction");
+
+        let accounts_iter = &mut accounts.iter();
+        let target_account = next_account_info(accounts_iter)?;
+        let authority = next_account_info(accounts_iter)?;
+        let recipient = next_account_info(accounts_iter)?;
+        if target_account.key == recipient.key {
+            msg!("The recipient cannot be the closed account");
+            return Err(ProgramError::InvalidArgument);
+        }
 
-        // Realloc the account to zero
-        target_account.resize(account_span)?;
+        {
+            let mut data = target_account.try_borrow_mut_data()?;
+            if StackAccount::<CAPACITY, LENGTH_SIZE>::is_valid(&data) {
+                let stack_account = StackAccount::<CAPACITY, LENGTH_SIZE>::cast_mut(*data);
+                check_authority(stack_account, authority)?;
+                check_not_verified(stack_account)?;
+            } else {
+                // Accounts of an older layout version, or that never got a
+                // header, have no authority to check, so the upgrade authority
+                // of the program closes them instead
+                if target_account.owner != program_id {
+                    return Err(ProgramError::IncorrectProgramId);
+                }
+                if data.starts_with(&CONSTANTS_HEADER) {
+                    msg!("Constants accounts cannot be closed");
+                    return Err(ProgramError::InvalidAccountData);
+                }
+                let program_data = next_account_info(accounts_iter)?;
+                check_upgrade_authority(program_id, program_data, authority)?;
+                msg!("Closing an account without a stack account header");
+            }
+        }
 
-        // Assign the account to the System Program
-        target_account.assign(system_program.key);
+        let lamports = target_account.lamports();
+        let recipient_lamports = recipient
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        **recipient.try_borrow_mut_lamports()? = recipient_lamports;
+        **target_account.try_borrow_mut_lamports()? = 0;
+
+        target_account.try_borrow_mut_data()?.fill(0);
+        target_account.resize(0)?;
+        target_account.assign(&solana_system_interface::program::ID);
+        msg!(
+            "Account closed, {} lamports sent to {}",
+            lamports,
+            recipient.key
+        );
 
         Ok(())
     }
 }
 
+/// Cast account data to a stack account, refusing data of another size or
+/// written with another layout version
+fn load_stack_account<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+    data: &mut [u8],
+) -> Result<&mut StackAccount<CAPACITY, LENGTH_SIZE>, ProgramError> {
+    if !StackAccount::<CAPACITY, LENGTH_SIZE>::is_valid(data) {
+        msg!("Not a stack account of version {}", ACCOUNT_VERSION);
+        return Err(VerifierError::UnsupportedAccountVersion.into());
+    }
+    Ok(StackAccount::cast_mut(data))
+}
+
+/// Fail if the verification held by the account already reached a final state
+fn check_not_final<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+    stack_account: &StackAccount<CAPACITY, LENGTH_SIZE>,
+) -> ProgramResult {
+    if stack_account.status.state()?.is_final() {
+        msg!("Verification already finished");
+        return Err(VerifierError::VerificationFinished.into());
+    }
+    Ok(())
+}
+
+/// Fail if the account holds a verified proof, the on-chain fact that the
+/// proof is valid, which must be kept as it is
+fn check_not_verified<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+    stack_account: &StackAccount<CAPACITY, LENGTH_SIZE>,
+) -> ProgramResult {
+    if stack_account.status.state()? == VerificationState::Verified {
+        msg!("Verified accounts cannot be closed or reset");
+        return Err(VerifierError::VerifiedFact.into());
+    }
+    Ok(())
+}
+
+/// Pin the constants account passed to the first step that passes one, and
+/// fail if a later step passes another, so that a verification cannot switch
+/// tables between steps
+fn pin_constants_account<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+    stack_account: &mut StackAccount<CAPACITY, LENGTH_SIZE>,
+    constants_account: &Pubkey,
+) -> ProgramResult {
+    if stack_account.constants_account == Pubkey::default() {
+        stack_account.constants_account = *constants_account;
+    } else if stack_account.constants_account != *constants_account {
+        msg!(
+            "Constants account {} does not match the pinned {}",
+            constants_account,
+            stack_account.constants_account
+        );
+        return Err(VerifierError::ConstantsMismatch.into());
+    }
+    Ok(())
+}
+
+/// Fail once the first step ran. The tasks, the data and the proof of a
+/// verification are only written before, so that a running verification
+/// cannot be steered into a result.
+fn check_pending<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+    stack_account: &StackAccount<CAPACITY, LENGTH_SIZE>,
+) -> ProgramResult {
+    if stack_account.status.state()? != VerificationState::Pending {
+        msg!("Verification already started");
+        return Err(VerifierError::VerificationStarted.into());
+    }
+    Ok(())
+}
+
+/// Fail unless `authority` is the recorded authority of the account and signed
+fn check_authority<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+    stack_account: &StackAccount<CAPACITY, LENGTH_SIZE>,
+    authority: &AccountInfo,
+) -> ProgramResult {
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !stack_account.has_authority() || stack_account.authority != *authority.key {
+        msg!("Signer {} is not the account authority", authority.key);
+        return Err(VerifierError::Unauthorized.into());
+    }
+    Ok(())
+}
+
+/// Constants region of a frozen constants account of the program
+fn load_frozen_constants<'a>(
+    program_id: &Pubkey,
+    account: &AccountInfo,
+    data: &'a [u8],
+) -> Result<&'a [u8], ProgramError> {
+    // Unlike the stack account, the constants account is only read, so the
+    // runtime would not catch an account of another owner
+    if account.owner != program_id {
+        msg!(
+            "Constants account {} is not owned by the program",
+            account.key
+        );
+        return Err(VerifierError::InvalidOwner.into());
+    }
+    let Some((constants_account, constants)) = ConstantsAccount::split(data) else {
+        msg!("Not a constants account of version {}", CONSTANTS_VERSION);
+        return Err(VerifierError::UnsupportedAccountVersion.into());
+    };
+    if !constants_account.is_frozen() {
+        msg!("Constants account {} is not frozen", account.key);
+        return Err(VerifierError::ConstantsNotFrozen.into());
+    }
+    Ok(constants)
+}
+
+/// Cast raw account data to a constants account that is not frozen yet and
+/// its constants region
+fn load_writable_constants(
+    data: &mut [u8],
+) -> Result<(&mut ConstantsAccount, &mut [u8]), ProgramError> {
+    let Some((constants_account, constants)) = ConstantsAccount::split_mut(data) else {
+        msg!("Not a constants account of version {}", CONSTANTS_VERSION);
+        return Err(VerifierError::UnsupportedAccountVersion.into());
+    };
+    if constants_account.is_frozen() {
+        msg!("Constants account is frozen");
+        return Err(VerifierError::ConstantsFrozen.into());
+    }
+    Ok((constants_account, constants))
+}
+
+/// Fail unless `authority` is the recorded authority of the constants account
+/// and signed
+fn check_constants_authority(
+    constants_account: &ConstantsAccount,
+    authority: &AccountInfo,
+) -> ProgramResult {
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if constants_account.authority != *authority.key {
+        msg!("Signer {} is not the constants authority", authority.key);
+        return Err(VerifierError::Unauthorized.into());
+    }
+    Ok(())
+}
+
+/// Fail unless `authority` signed and is the upgrade authority recorded in
+/// the program data account of the program
+fn check_upgrade_authority(
+    program_id: &Pubkey,
+    program_data: &AccountInfo,
+    authority: &AccountInfo,
+) -> ProgramResult {
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *program_data.key != get_program_data_address(program_id)
+        || !solana_sdk_ids::bpf_loader_upgradeable::check_id(program_data.owner)
+    {
+        msg!(
+            "Account {} is not the program data account",
+            program_data.key
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let data = program_data.try_borrow_data()?;
+    let metadata = data
+        .get(..UpgradeableLoaderState::size_of_programdata_metadata())
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let upgrade_authority = match bincode::deserialize(metadata) {
+        Ok(UpgradeableLoaderState::ProgramData {
+            upgrade_authority_address,
+            ..
+        }) => upgrade_authority_address,
+        _ => return Err(ProgramError::InvalidAccountData),
+    };
+    if upgrade_authority != Some(*authority.key) {
+        msg!("Signer {} is not the upgrade authority", authority.key);
+        return Err(VerifierError::Unauthorized.into());
+    }
+    Ok(())
+}
+
 /// Instruction processor
 pub fn process_instruction(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
@@ -151,19 +586,53 @@ pub fn process_instruction(
     // Unpack the instruction
     let instruction = VerifierInstruction::try_from_slice(instruction_data)?;
 
-    // Process the instruction
+    // The size of the stack account selects its layout, every other size is
+    // handled as the default layout and refused when loading it
+    let data_len = accounts.first().map_or(0, |account| account.data_len());
+    if data_len == size_of::<LargeStackAccount>() {
+        process::<LARGE_CAPACITY, LARGE_LENGTH_SIZE>(program_id, accounts, instruction)
+    } else {
+        process::<DEFAULT_CAPACITY, DEFAULT_LENGTH_SIZE>(program_id, accounts, instruction)
+    }
+}
+
+/// Process an instruction against a stack account of the given layout
+///
+/// Constants instructions take no stack account and ignore the layout.
+fn process<const CAPACITY: usize, const LENGTH_SIZE: usize>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction: VerifierInstruction,
+) -> ProgramResult {
     match instruction {
         VerifierInstruction::SetAccountData(offset, data) => {
-            Processor::process_set_account_data(accounts, offset, data)
+            Processor::process_set_account_data::<CAPACITY, LENGTH_SIZE>(accounts, offset, data)
         }
         VerifierInstruction::PushTask(task_data) => {
-            Processor::process_push_task(accounts, task_data)
+            Processor::process_push_task::<CAPACITY, LENGTH_SIZE>(accounts, task_data)
         }
         VerifierInstruction::PushData(data_payload) => {
-            Processor::process_push_data(accounts, data_payload)
+            Processor::process_push_data::<CAPACITY, LENGTH_SIZE>(accounts, data_payload)
+        }
+        VerifierInstruction::Execute(step) => {
+            Processor::process_execute::<CAPACITY, LENGTH_SIZE>(program_id, accounts, step)
         }
-        VerifierInstruction::Execute(nonce) => Processor::process_execute(accounts, nonce),
 
-        VerifierInstruction::Close => Processor::close(accounts),
+        VerifierInstruction::Close => {
+            Processor::close::<CAPACITY, LENGTH_SIZE>(program_id, accounts)
+        }
+        VerifierInstruction::Initialize => {
+            Processor::process_initialize::<CAPACITY, LENGTH_SIZE>(accounts)
+        }
+        VerifierInstruction::Reset(clear_proof) => {
+            Processor::process_reset::<CAPACITY, LENGTH_SIZE>(accounts, clear_proof)
+        }
+        VerifierInstruction::InitializeConstants => {
+            Processor::process_initialize_constants(program_id, accounts)
+        }
+        VerifierInstruction::SetConstantsData(offset, data) => {
+            Processor::process_set_constants_data(accounts, offset, data)
+        }
+        VerifierInstruction::FreezeConstants => Processor::process_freeze_constants(accounts),
     }
 }
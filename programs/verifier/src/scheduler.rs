@@ -1,21 +1,144 @@
-use crate::state::BidirectionalStackAccount;
-use utils::{BidirectionalStack, Executable, Scheduler};
+use crate::error::VerifierError;
+use crate::state::{StackAccount, VerificationState};
+use stark::stark_proof::{verify::Verify, VerifyPublicInput};
+use utils::{
+    frame::{frame_entry, FrameStack, FRAME_MARKER_SIZE, FRAME_MARKER_UNSET},
+    BidirectionalStack, Executable, Scheduler, WithConstants,
+};
 
-// Include the generated dispatch code
-include!(concat!(env!("OUT_DIR"), "/verifier_executable_dispatch.rs"));
+// Every task that may run on-chain has to be listed here
+utils::register_tasks!(
+    utils::Reject,
+    arithmetic::add::Add,
+    arithmetic::exp::Exp,
+    arithmetic::exp::ExpInternal,
+    arithmetic::fib::Fibonacci,
+    arithmetic::fib::FibonacciCombiner,
+    arithmetic::increment::Increment,
+    arithmetic::mul::Mul,
+    arithmetic::mul::MulInternal,
+    stark::keccak::KeccakHash,
+    stark::pedersen::PedersenHash,
+    stark::poseidon::PoseidonHashMany,
+    stark::poseidon::PoseidonHashMainPage,
+    stark::poseidon::hades::HadesPermutation,
+    stark::stark_proof::HashPublicInputs,
+    stark::stark_proof::VerifyPublicInput,
+    stark::stark_proof::get_hash::GetHash,
+    stark::stark_proof::stark_commit::StarkCommit,
+    stark::stark_proof::stark_verify::StarkVerify,
+    stark::stark_proof::validate_public_input::ValidatePublicInput,
+    stark::stark_proof::verify::Verify,
+);
 
-impl Scheduler for BidirectionalStackAccount {}
+impl<const CAPACITY: usize, const LENGTH_SIZE: usize> FrameStack
+    for StackAccount<CAPACITY, LENGTH_SIZE>
+{
+    fn front_offset(&self, items: usize) -> Option<usize> {
+        StackAccount::front_offset(self, items)
+    }
+}
+
+impl<const CAPACITY: usize, const LENGTH_SIZE: usize> Scheduler
+    for StackAccount<CAPACITY, LENGTH_SIZE>
+{
+    fn push_task<T: Executable>(&mut self, task: T) {
+        self.schedule(&task.to_vec_with_type_tag()).unwrap();
+    }
+}
 
-impl BidirectionalStackAccount {
-    pub fn execute(&mut self) {
-        let (tasks, is_finished) = execute(self);
+impl<const CAPACITY: usize, const LENGTH_SIZE: usize> StackAccount<CAPACITY, LENGTH_SIZE> {
+    /// Push a task with its type tag to the back stack, followed by its frame
+    /// marker, see `frame_entry`
+    pub fn schedule(&mut self, task: &[u8]) -> Result<(), VerifierError> {
+        self.push_back(&frame_entry(task))
+    }
 
-        if is_finished {
-            self.pop_back();
+    /// Type tag of the task on top of the back stack if it is a verification
+    /// task that did not run yet and no other task is scheduled below it
+    ///
+    /// Only such a root, found by the first step, gets its result recorded:
+    /// its completion then empties the back stack, and no task or data can
+    /// be pushed once the first step ran. Of the roots, only `Verify` checks
+    /// the proof and leaves the account `Verified`.
+    fn verification_root(&self) -> Option<u32> {
+        let entry = self.borrow_back();
+        if self.back_index + LENGTH_SIZE + entry.len() != CAPACITY {
+            return None;
+        }
+        let roots = [
+            Verify::new().to_vec_with_type_tag(),
+            VerifyPublicInput::new().to_vec_with_type_tag(),
+        ];
+        let (task, marker) = entry.split_at_checked(entry.len().checked_sub(FRAME_MARKER_SIZE)?)?;
+        if marker != FRAME_MARKER_UNSET.to_be_bytes() || !roots.iter().any(|root| root == task) {
+            return None;
         }
+        Some(u32::from_be_bytes(task[0..4].try_into().unwrap()))
+    }
 
-        for task in tasks.iter().rev() {
-            let _ = self.push_back(task);
+    /// Copy the program and output hashes the verification root left on top
+    /// of the front stack into the status, where events and clients read them
+    fn record_result(&mut self) {
+        let hash = |depth| {
+            self.front_item(depth)
+                .and_then(|item| <[u8; 32]>::try_from(item).ok())
+        };
+        if let (Some(program_hash), Some(output_hash)) = (hash(0), hash(1)) {
+            self.status.program_hash = program_hash;
+            self.status.output_hash = output_hash;
         }
     }
+
+    /// Record a rejection by the task with `type_tag`. A rejection is final:
+    /// no other task runs.
+    fn reject(&mut self, code: u32, type_tag: u32) {
+        self.status.reject(code, type_tag);
+        self.clear_back();
+    }
+
+    /// Execute the task on top of the back stack, returning its type tag and
+    /// whether it completed in this step, or `EmptyStack` if no task is left
+    pub fn execute(&mut self) -> Result<(u32, bool), VerifierError> {
+        self.execute_with_constants(&[])
+    }
+
+    /// `execute` with the constants region of a constants account lent to
+    /// the task
+    pub fn execute_with_constants(
+        &mut self,
+        constants: &[u8],
+    ) -> Result<(u32, bool), VerifierError> {
+        if self.is_empty_back() {
+            return Err(VerifierError::EmptyStack);
+        }
+        let type_tag = u32::from_be_bytes(self.borrow_back()[0..4].try_into().unwrap());
+        if self.status.state == VerificationState::Pending as u32 {
+            self.status.root_task = self.verification_root().unwrap_or_default();
+        }
+        self.executed_steps += 1;
+        let is_finished = match step(&mut WithConstants::new(self, constants)) {
+            Ok(is_finished) => is_finished,
+            Err(code) => {
+                self.reject(code, type_tag);
+                return Ok((type_tag, false));
+            }
+        };
+
+        // Only the completion of a `Verify` root verifies the proof
+        if !self.is_empty_back() {
+            self.status.set_state(VerificationState::Running);
+        } else {
+            if self.status.root_task != 0 {
+                self.record_result();
+            }
+            if self.status.root_task == Verify::TYPE_TAG {
+                self.status.set_state(VerificationState::Verified);
+            } else {
+                self.status.set_state(VerificationState::Completed);
+            }
+        }
+
+        Ok((type_tag, is_finished))
+    }
 }
@@ -1,48 +1,331 @@
 use crate::error::VerifierError;
+use solana_program::pubkey::Pubkey;
 use stark::swiftness::stark::types::cast_struct_to_slice_mut;
 use stark::swiftness::stark::types::{cast_struct_to_slice, StarkProof};
 use utils::{AccountCast, BidirectionalStack};
 
-const CAPACITY: usize = 65536;
-const LENGTH_SIZE: usize = 2;
+/// Buffer size of a default stack account
+pub const DEFAULT_CAPACITY: usize = 65536;
+/// Width of the length suffix of every item of a default stack account,
+/// which limits items to 65535 bytes
+pub const DEFAULT_LENGTH_SIZE: usize = 2;
+/// Buffer size of a large stack account, for workloads whose stack or single
+/// items outgrow the default account
+pub const LARGE_CAPACITY: usize = 1 << 20;
+/// Width of the length suffix of every item of a large stack account
+pub const LARGE_LENGTH_SIZE: usize = 4;
+
+/// Stack account with the default capacity, used unless an account is created
+/// with the size of another layout
+pub type BidirectionalStackAccount = StackAccount<DEFAULT_CAPACITY, DEFAULT_LENGTH_SIZE>;
+/// Stack account with `LARGE_CAPACITY` bytes of stack and items of any size
+/// that fits in it
+pub type LargeStackAccount = StackAccount<LARGE_CAPACITY, LARGE_LENGTH_SIZE>;
+
+// Solana accounts hold at most 10 MiB of data
+const _: () = assert!(
+    size_of::<LargeStackAccount>() <= 10 * 1024 * 1024,
+    "large stack accounts must fit in the maximum account size"
+);
+
+/// Magic bytes every stack account starts with
+pub const ACCOUNT_MAGIC: [u8; 4] = *b"SVSA";
+/// Version of the account layout, which includes the `StarkProof` layout, its
+/// capacities and the layouts of the tasks an in-flight verification keeps on
+/// its back stack. Bump it on any change to either, so that accounts written
+/// with another layout are refused instead of misread.
+pub const ACCOUNT_VERSION: u32 = 4;
+/// Header written by `Initialize` and checked whenever an account is cast
+pub const ACCOUNT_HEADER: [u8; 8] = account_header(ACCOUNT_VERSION);
+
+const fn account_header(version: u32) -> [u8; 8] {
+    header(ACCOUNT_MAGIC, version)
+}
+
+const fn header(magic: [u8; 4], version: u32) -> [u8; 8] {
+    let version = version.to_le_bytes();
+    [
+        magic[0], magic[1], magic[2], magic[3], version[0], version[1], version[2], version[3],
+    ]
+}
+
+/// Layout version of raw account data, `None` if it does not start with the
+/// magic bytes, like a newly created, zero-filled account
+pub fn account_version(data: &[u8]) -> Option<u32> {
+    let header = data.get(0..ACCOUNT_HEADER.len())?;
+    if header[0..4] != ACCOUNT_MAGIC {
+        return None;
+    }
+    Some(u32::from_le_bytes(header[4..8].try_into().unwrap()))
+}
+
+/// Magic bytes every constants account starts with
+pub const CONSTANTS_MAGIC: [u8; 4] = *b"SVCA";
+/// Version of the fixed part of a constants account, see `ConstantsAccount`
+pub const CONSTANTS_VERSION: u32 = 1;
+/// Header written by `InitializeConstants` and checked whenever the constants
+/// of an account are read
+pub const CONSTANTS_HEADER: [u8; 8] = header(CONSTANTS_MAGIC, CONSTANTS_VERSION);
+
+/// Fixed part of a read-only constants account, such as the Pedersen tables,
+/// kept out of the program binary
+///
+/// It is followed by the constants region up to the end of the account, laid
+/// out as described in `utils::constants`. Only the upgrade authority of the
+/// program can initialize one, so the constants of a frozen account are as
+/// trusted as the program itself.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ConstantsAccount {
+    /// Magic bytes and layout version, see `CONSTANTS_HEADER`
+    pub header: [u8; 8],
+    /// Signer allowed to write the constants until they are frozen
+    pub authority: Pubkey,
+    /// Non-zero once `FreezeConstants` made the constants immutable
+    pub frozen: u64,
+}
+
+// Tables in the constants region are cast in place
+const _: () = assert!(
+    size_of::<ConstantsAccount>() % utils::constants::TABLE_ALIGN == 0,
+    "the constants region must stay aligned"
+);
+
+impl AccountCast for ConstantsAccount {
+    const HEADER: &'static [u8] = &CONSTANTS_HEADER;
+}
+
+impl ConstantsAccount {
+    /// Split raw account data into its fixed part and its constants region,
+    /// `None` if it is not a constants account
+    pub fn split(data: &[u8]) -> Option<(&Self, &[u8])> {
+        let (fixed, constants) = data.split_at_checked(size_of::<Self>())?;
+        Self::is_valid(fixed).then(|| (Self::cast(fixed), constants))
+    }
+
+    /// Mutable `split`
+    pub fn split_mut(data: &mut [u8]) -> Option<(&mut Self, &mut [u8])> {
+        let (fixed, constants) = data.split_at_mut_checked(size_of::<Self>())?;
+        Self::is_valid(fixed).then(|| (Self::cast_mut(fixed), constants))
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen != 0
+    }
+}
 
 /// Define the type of state stored in accounts
+///
+/// `CAPACITY` is the size of the buffer shared by both stacks and
+/// `LENGTH_SIZE` the number of bytes of the length stored with every item.
+/// Every layout the program accepts is chosen by the size of the account it
+/// is created with, see `BidirectionalStackAccount` and `LargeStackAccount`.
 #[repr(C)]
 #[derive(Debug)]
-pub struct BidirectionalStackAccount {
+pub struct StackAccount<const CAPACITY: usize, const LENGTH_SIZE: usize> {
+    /// Magic bytes and layout version, see `ACCOUNT_HEADER`
+    pub header: [u8; 8],
     pub front_index: usize,
     pub back_index: usize,
+    /// Number of `Execute` steps applied so far, the next `Execute` must carry it
+    pub executed_steps: u64,
+    pub status: VerificationStatus,
+    /// Signer allowed to reset and close the account, unset until `Initialize`
+    pub authority: Pubkey,
+    /// Constants account lent to the tasks, pinned by the first `Execute`
+    /// step that passes one so that every step reads the same constants
+    pub constants_account: Pubkey,
     pub proof: StarkProof,
     pub buffer: [u8; CAPACITY],
 }
-impl Default for BidirectionalStackAccount {
+/// Lifecycle of the verification held by an account
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationState {
+    /// No task has been executed yet
+    Pending = 0,
+    /// Tasks are left on the stack
+    Running = 1,
+    /// The `Verify` root finished, the results are on the front of the stack.
+    /// This is the only state that makes the account a proof fact.
+    Verified = 2,
+    /// A task rejected the proof, see the error code and failed task
+    Rejected = 3,
+    /// All tasks completed, but the root was not `Verify` (see
+    /// `VerificationStatus::root_task`), so the proof was not verified
+    Completed = 4,
+}
+
+impl VerificationState {
+    /// Whether the verification can no longer progress
+    pub fn is_final(self) -> bool {
+        matches!(self, Self::Verified | Self::Rejected | Self::Completed)
+    }
+}
+
+impl TryFrom<u32> for VerificationState {
+    type Error = VerifierError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Pending),
+            1 => Ok(Self::Running),
+            2 => Ok(Self::Verified),
+            3 => Ok(Self::Rejected),
+            4 => Ok(Self::Completed),
+            _ => Err(VerifierError::InvalidData(format!(
+                "Invalid verification state {value}"
+            ))),
+        }
+    }
+}
+
+/// Status header of an account. Fields are plain integers because the account
+/// bytes can hold any value, use `state()` to read the typed state.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationStatus {
+    pub state: u32,
+    /// Reason of the rejection, defined by the rejecting task
+    pub error_code: u32,
+    /// Type tag of the task that rejected the proof
+    pub failed_task: u32,
+    /// Type tag of the verification task at the root of the account, zero if
+    /// the first step found any other task, a verification task that already
+    /// ran, or more than one task on the back stack
+    pub root_task: u32,
+    /// Program hash as a big-endian felt, recorded from the front stack when
+    /// the verification root completes
+    pub program_hash: [u8; 32],
+    /// Output hash, recorded with `program_hash`
+    pub output_hash: [u8; 32],
+}
+
+impl VerificationStatus {
+    pub fn state(&self) -> Result<VerificationState, VerifierError> {
+        VerificationState::try_from(self.state)
+    }
+
+    pub fn set_state(&mut self, state: VerificationState) {
+        self.state = state as u32;
+    }
+
+    pub fn reject(&mut self, error_code: u32, failed_task: u32) {
+        self.set_state(VerificationState::Rejected);
+        self.error_code = error_code;
+        self.failed_task = failed_task;
+    }
+}
+
+impl<const CAPACITY: usize, const LENGTH_SIZE: usize> Default
+    for StackAccount<CAPACITY, LENGTH_SIZE>
+{
     fn default() -> Self {
         Self {
+            header: ACCOUNT_HEADER,
             proof: StarkProof::default(),
             front_index: 0,
             back_index: CAPACITY,
+            executed_steps: 0,
+            status: VerificationStatus::default(),
+            authority: Pubkey::default(),
+            constants_account: Pubkey::default(),
             buffer: [0; CAPACITY],
         }
     }
 }
 
-impl BidirectionalStackAccount {
+impl<const CAPACITY: usize, const LENGTH_SIZE: usize> StackAccount<CAPACITY, LENGTH_SIZE> {
+    /// Drop all remaining tasks
+    pub fn clear_back(&mut self) {
+        self.back_index = CAPACITY;
+    }
+
+    /// Empty both stacks and return to the `Pending` state, keeping the
+    /// authority and unpinning the constants account. The proof is zeroed
+    /// too when `clear_proof` is set.
+    ///
+    /// Fields are cleared in place, the account is too large to build a
+    /// default value on the program stack.
+    pub fn reset(&mut self, clear_proof: bool) {
+        self.front_index = 0;
+        self.back_index = CAPACITY;
+        self.executed_steps = 0;
+        self.status = VerificationStatus::default();
+        self.constants_account = Pubkey::default();
+        if clear_proof {
+            self.get_proof_bytes_mut().fill(0);
+        }
+    }
+
+    /// Whether an authority was recorded with `Initialize`
+    pub fn has_authority(&self) -> bool {
+        self.authority != Pubkey::default()
+    }
+
+    /// Front index below the top `items` items of the front stack, or `None`
+    /// if it holds fewer items
+    pub fn front_offset(&self, items: usize) -> Option<usize> {
+        let mut index = self.front_index;
+        for _ in 0..items {
+            if index < LENGTH_SIZE {
+                return None;
+            }
+            let mut data_length = 0_usize;
+            for _ in 0..LENGTH_SIZE {
+                index -= 1;
+                data_length = (data_length << 8) | usize::from(self.buffer[index]);
+            }
+            index = index.checked_sub(data_length)?;
+        }
+        Some(index)
+    }
+
+    /// Item `depth` items below the top of the front stack, `None` if the
+    /// front stack holds fewer items
+    pub fn front_item(&self, depth: usize) -> Option<&[u8]> {
+        let start = self.front_offset(depth + 1)?;
+        let end = self.front_offset(depth)? - LENGTH_SIZE;
+        self.buffer.get(start..end)
+    }
+
+    /// Fail if the length of an item does not fit in its length suffix
+    fn check_item_length(data: &[u8]) -> Result<(), VerifierError> {
+        if LENGTH_SIZE < size_of::<usize>() && data.len() >> (LENGTH_SIZE * 8) != 0 {
+            return Err(VerifierError::InvalidTaskLength);
+        }
+        Ok(())
+    }
+
     pub fn simulate(&mut self) -> u128 {
+        self.simulate_with_constants(&[])
+    }
+
+    /// Run all remaining tasks with the constants region of a constants
+    /// account, returning the number of steps
+    pub fn simulate_with_constants(&mut self, constants: &[u8]) -> u128 {
         let mut simulation_steps = 0;
         while !self.is_empty_back() {
-            self.execute();
+            self.execute_with_constants(constants).unwrap();
             simulation_steps += 1;
         }
         simulation_steps
     }
 }
 
-impl AccountCast for BidirectionalStackAccount {}
+impl<const CAPACITY: usize, const LENGTH_SIZE: usize> AccountCast
+    for StackAccount<CAPACITY, LENGTH_SIZE>
+{
+    const HEADER: &'static [u8] = &ACCOUNT_HEADER;
+}
 
-impl BidirectionalStack for BidirectionalStackAccount {
+impl<const CAPACITY: usize, const LENGTH_SIZE: usize> BidirectionalStack
+    for StackAccount<CAPACITY, LENGTH_SIZE>
+{
     type Error = VerifierError;
 
     fn push_front(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        Self::check_item_length(data)?;
         for byte in data {
             self.buffer[self.front_index] = *byte;
             self.front_index = self.front_index.saturating_add(1);
@@ -58,6 +341,7 @@ impl BidirectionalStack for BidirectionalStackAccount {
     }
 
     fn push_back(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        Self::check_item_length(data)?;
         for byte in data.iter().rev() {
             self.back_index = self.back_index.saturating_sub(1);
             self.buffer[self.back_index] = *byte;
@@ -157,14 +441,36 @@ impl BidirectionalStack for BidirectionalStackAccount {
 
 #[cfg(test)]
 mod tests {
-    use crate::state::{BidirectionalStackAccount, CAPACITY};
-    use utils::BidirectionalStack;
+    use crate::error::VerifierError;
+    use crate::state::{
+        account_header, account_version, BidirectionalStackAccount, ConstantsAccount,
+        LargeStackAccount, VerificationState, VerificationStatus, ACCOUNT_HEADER, ACCOUNT_VERSION,
+        CONSTANTS_HEADER, DEFAULT_CAPACITY as CAPACITY,
+    };
+    use solana_program::pubkey::Pubkey;
+    use stark::felt::Felt;
+    use utils::{AccountCast, BidirectionalStack};
+
+    #[test]
+    fn test_front_item() {
+        let mut stack = BidirectionalStackAccount::default();
+        assert_eq!(stack.front_item(0), None);
+
+        stack.push_front(&[2; 32]).unwrap();
+        stack.push_front(&[1, 2, 3]).unwrap();
+        assert_eq!(stack.front_item(0), Some(&[1, 2, 3][..]));
+        assert_eq!(stack.front_item(1), Some(&[2; 32][..]));
+        assert_eq!(stack.front_item(2), None);
+        assert_eq!(stack.borrow_front(), &[1, 2, 3]);
+    }
 
     #[test]
     fn test_default() {
         let stack = BidirectionalStackAccount::default();
         assert_eq!(stack.front_index, 0);
         assert_eq!(stack.back_index, CAPACITY);
+        assert_eq!(stack.executed_steps, 0);
+        assert_eq!(stack.status.state().unwrap(), VerificationState::Pending);
         assert_eq!(stack.buffer, [0; CAPACITY]);
     }
 
@@ -206,7 +512,10 @@ mod tests {
         stack.push_front(&data).unwrap();
 
         // Verify front_index moved
-        assert_eq!(stack.front_index, data.len() + crate::state::LENGTH_SIZE);
+        assert_eq!(
+            stack.front_index,
+            data.len() + crate::state::DEFAULT_LENGTH_SIZE
+        );
 
         // Pop data
         stack.pop_front();
@@ -229,7 +538,7 @@ mod tests {
         // Verify back_index moved
         assert_eq!(
             stack.back_index,
-            CAPACITY - data.len() - crate::state::LENGTH_SIZE
+            CAPACITY - data.len() - crate::state::DEFAULT_LENGTH_SIZE
         );
 
         // Pop data
@@ -491,10 +800,107 @@ mod tests {
         assert_eq!(stack.borrow_back().len(), safe_capacity);
 
         // The front_index and back_index should reflect the data size + length bytes
-        assert_eq!(stack.front_index, safe_capacity + crate::state::LENGTH_SIZE);
+        assert_eq!(
+            stack.front_index,
+            safe_capacity + crate::state::DEFAULT_LENGTH_SIZE
+        );
         assert_eq!(
             stack.back_index,
-            CAPACITY - safe_capacity - crate::state::LENGTH_SIZE
+            CAPACITY - safe_capacity - crate::state::DEFAULT_LENGTH_SIZE
+        );
+    }
+
+    #[test]
+    fn test_front_offset() {
+        let mut stack = BidirectionalStackAccount::default();
+        assert_eq!(stack.front_offset(0), Some(0));
+        assert_eq!(stack.front_offset(1), None);
+
+        stack.push_front(&[1; 300]).unwrap();
+        stack.push_front(&[2; 3]).unwrap();
+        assert_eq!(
+            stack.front_offset(1),
+            Some(300 + crate::state::DEFAULT_LENGTH_SIZE)
         );
+        assert_eq!(stack.front_offset(2), Some(0));
+        assert_eq!(stack.front_offset(3), None);
+    }
+
+    #[test]
+    fn test_reset_keeps_authority() {
+        let mut stack = BidirectionalStackAccount {
+            authority: Pubkey::new_unique(),
+            ..Default::default()
+        };
+        stack.proof.public_input.layout = Felt::ONE;
+        stack.push_front(&[1, 2, 3]).unwrap();
+        stack.push_back(&[4, 5, 6]).unwrap();
+        stack.executed_steps = 3;
+        stack.status.reject(1, 2);
+
+        stack.reset(false);
+        assert!(stack.is_empty_front());
+        assert!(stack.is_empty_back());
+        assert_eq!(stack.executed_steps, 0);
+        assert_eq!(stack.status, VerificationStatus::default());
+        assert!(stack.has_authority());
+        assert_eq!(stack.proof.public_input.layout, Felt::ONE);
+
+        stack.reset(true);
+        assert_eq!(stack.proof.public_input.layout, Felt::ZERO);
+    }
+
+    #[test]
+    fn test_account_header() {
+        let mut data = vec![0u8; std::mem::size_of::<BidirectionalStackAccount>()];
+        assert_eq!(account_version(&data), None);
+        assert!(!BidirectionalStackAccount::is_valid(&data));
+
+        data[0..8].copy_from_slice(&account_header(ACCOUNT_VERSION + 1));
+        assert_eq!(account_version(&data), Some(ACCOUNT_VERSION + 1));
+        assert!(!BidirectionalStackAccount::is_valid(&data));
+
+        data[0..8].copy_from_slice(&ACCOUNT_HEADER);
+        assert!(BidirectionalStackAccount::is_valid(&data));
+        assert!(!BidirectionalStackAccount::is_valid(&data[1..]));
+    }
+
+    #[test]
+    fn test_constants_account() {
+        let mut data = vec![0u8; size_of::<ConstantsAccount>() + 16];
+        assert!(ConstantsAccount::split(&data).is_none());
+        assert!(ConstantsAccount::split(&data[..8]).is_none());
+
+        data[0..8].copy_from_slice(&ACCOUNT_HEADER);
+        assert!(ConstantsAccount::split(&data).is_none());
+
+        data[0..8].copy_from_slice(&CONSTANTS_HEADER);
+        let (account, constants) = ConstantsAccount::split_mut(&mut data).unwrap();
+        assert!(!account.is_frozen());
+        account.frozen = 1;
+        constants.fill(7);
+
+        let (account, constants) = ConstantsAccount::split(&data).unwrap();
+        assert!(account.is_frozen());
+        assert_eq!(constants, &[7; 16]);
+    }
+
+    #[test]
+    fn test_item_length_limit() {
+        let mut stack = BidirectionalStackAccount::default();
+        let data = vec![1u8; 1 << 16];
+        assert!(matches!(
+            stack.push_front(&data),
+            Err(VerifierError::InvalidTaskLength)
+        ));
+        assert!(stack.is_empty_front());
+
+        // A large account is too big for the test thread stack
+        let mut account = vec![0u8; size_of::<LargeStackAccount>()];
+        account[..ACCOUNT_HEADER.len()].copy_from_slice(&ACCOUNT_HEADER);
+        let stack = LargeStackAccount::cast_mut(&mut account);
+        stack.reset(false);
+        stack.push_front(&data).unwrap();
+        assert_eq!(stack.borrow_front(), &data[..]);
     }
 }
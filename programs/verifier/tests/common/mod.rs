@@ -0,0 +1,22 @@
+use utils::{BidirectionalStack, Executable, Scheduler};
+use verifier::state::BidirectionalStackAccount;
+
+/// Run `task` to completion on a new stack holding the inputs written by
+/// `push_input`, lending it the tables in `constants` if any. Returns the
+/// stack with the results of the task and the number of steps it took.
+pub fn run_task<T: Executable>(
+    push_input: impl FnOnce(&mut BidirectionalStackAccount),
+    task: T,
+    constants: &[u8],
+) -> (BidirectionalStackAccount, usize) {
+    let mut stack = BidirectionalStackAccount::default();
+    push_input(&mut stack);
+    stack.push_task(task);
+
+    let mut steps = 0;
+    while !stack.is_empty_back() {
+        stack.execute_with_constants(constants).unwrap();
+        steps += 1;
+    }
+    (stack, steps)
+}
@@ -0,0 +1,33 @@
+use stark::felt::Felt;
+use stark::pedersen::constants::SHIFT_POINT;
+use stark::stack::FeltStack;
+use utils::BidirectionalStack;
+use verifier::state::BidirectionalStackAccount;
+
+#[test]
+fn push_and_pop_felts() {
+    let mut stack = BidirectionalStackAccount::default();
+    let felts = [Felt::from(1u64), Felt::from(2u64), Felt::from(3u64)];
+
+    stack.push_felts(&felts);
+    assert_eq!(stack.peek_felt(), felts[0]);
+    assert_eq!(stack.pop_felts::<3>(), felts);
+    assert!(stack.is_empty_front());
+}
+
+#[test]
+fn push_and_pop_point() {
+    let mut stack = BidirectionalStackAccount::default();
+
+    stack.push_point(&SHIFT_POINT);
+    assert!(stack.pop_point() == SHIFT_POINT);
+    assert!(stack.is_empty_front());
+}
+
+#[test]
+#[should_panic(expected = "front stack item is not a felt")]
+fn pop_felt_rejects_other_items() {
+    let mut stack = BidirectionalStackAccount::default();
+    stack.push_front(&[1, 2, 3]).unwrap();
+    stack.pop_felt();
+}
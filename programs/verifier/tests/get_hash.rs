@@ -22,7 +22,7 @@ fn get_hash() {
 
     stack.push_task(GetHash::new(Felt::ZERO));
     while !stack.is_empty_back() {
-        stack.execute();
+        stack.execute().unwrap();
     }
 
     let expected = Felt::from_hex_unchecked(
@@ -19,7 +19,7 @@ fn test_hades_permutation() {
     // Execute until completion
     let mut steps = 0;
     while !stack.is_empty_back() {
-        stack.execute();
+        stack.execute().unwrap();
         steps += 1;
     }
 
@@ -51,3 +51,25 @@ fn test_hades_permutation() {
     assert!(steps > 0, "Should have executed at least one step");
     assert_eq!(stack.front_index, 0, "Stack should be empty after test");
 }
+
+#[test]
+fn test_hades_permute() {
+    let state = [
+        Felt::from_hex("0x9").unwrap(),
+        Felt::from_hex("0xb").unwrap(),
+        Felt::from_hex("0x2").unwrap(),
+    ];
+
+    // Same result as the task, run in a single call
+    assert_eq!(
+        HadesPermutation::permute(state),
+        [
+            Felt::from_hex("0x510f3a3faf4084e3b1e95fd44c30746271b48723f7ea9c8be6a9b6b5408e7e6")
+                .unwrap(),
+            Felt::from_hex("0x4f511749bd4101266904288021211333fb0a514cb15381af087462fa46e6bd9")
+                .unwrap(),
+            Felt::from_hex("0x186f6dd1a6e79cb1b66d505574c349272cd35c07c223351a0990410798bb9d8")
+                .unwrap(),
+        ]
+    );
+}
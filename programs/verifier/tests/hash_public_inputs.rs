@@ -18,7 +18,7 @@ fn hash_public_inputs_basic() {
 
     stack.push_task(VerifyPublicInput::new());
     while !stack.is_empty_back() {
-        stack.execute();
+        stack.execute().unwrap();
     }
     let result_program_hash = Felt::from_bytes_be_slice(stack.borrow_front());
     stack.pop_front();
@@ -0,0 +1,103 @@
+mod common;
+
+use stark::{
+    felt::Felt,
+    keccak::{keccak_felts, masked_160, FeltEncoding, KeccakHash, KeccakOutput},
+    swiftness::commitment::hash_node,
+};
+use verifier::state::BidirectionalStackAccount;
+
+use common::run_task;
+
+/// Keccak-256 of 32 zero bytes
+const ZERO_WORD_DIGEST: &str = "290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563";
+
+fn hex_bytes(hex: &str) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+    }
+    bytes
+}
+
+fn run_keccak(inputs: &[Felt], task: KeccakHash) -> BidirectionalStackAccount {
+    run_task(|stack| KeccakHash::push_input(inputs, stack), task, &[]).0
+}
+
+#[test]
+fn keccak_known_digest() {
+    let digest = keccak_felts(&[Felt::ZERO], FeltEncoding::BigEndian);
+    assert_eq!(digest, hex_bytes(ZERO_WORD_DIGEST));
+    assert_eq!(
+        masked_160(&digest),
+        Felt::from_hex_unchecked("0x88386fc84ba6bc95484008f6362f93160ef3e563")
+    );
+}
+
+#[test]
+fn keccak_montgomery_encoding() {
+    // The Montgomery form of one is 2^256 mod p
+    assert_eq!(
+        FeltEncoding::Montgomery.encode(&Felt::ONE),
+        hex_bytes("07fffffffffffdf0ffffffffffffffffffffffffffffffffffffffffffffffe1")
+    );
+    assert_eq!(FeltEncoding::Montgomery.encode(&Felt::ZERO), [0; 32]);
+    // and the one of -1 is p - 2^256 mod p
+    let mut minus_r = [0u8; 32];
+    minus_r[6..8].copy_from_slice(&544u16.to_be_bytes());
+    minus_r[31] = 32;
+    assert_eq!(FeltEncoding::Montgomery.encode(&Felt::MAX), minus_r);
+
+    let x = Felt::from_hex_unchecked("0x123456789abcdef");
+    let r = Felt::from_bytes_be(&FeltEncoding::Montgomery.encode(&Felt::ONE));
+    assert_eq!(FeltEncoding::Montgomery.encode(&x), (x * r).to_bytes_be());
+}
+
+#[test]
+fn keccak_hash_task() {
+    let inputs = [Felt::ONE, Felt::TWO, Felt::THREE];
+
+    for encoding in [FeltEncoding::BigEndian, FeltEncoding::Montgomery] {
+        let expected = keccak_felts(&inputs, encoding);
+
+        let task = KeccakHash::with_encoding(inputs.len(), encoding, KeccakOutput::Masked160);
+        let mut stack = run_keccak(&inputs, task);
+        assert_eq!(KeccakHash::pop_result(&mut stack), masked_160(&expected));
+        assert_eq!(stack.front_index, 0);
+
+        let task = KeccakHash::with_encoding(inputs.len(), encoding, KeccakOutput::Digest);
+        let mut stack = run_keccak(&inputs, task);
+        assert_eq!(KeccakHash::pop_digest(&mut stack), expected);
+        assert_eq!(stack.front_index, 0);
+    }
+}
+
+#[test]
+fn keccak_hash_concatenates_in_order() {
+    let mut bytes = [0u8; 64];
+    bytes[31] = 1;
+    bytes[63] = 2;
+    let expected = solana_keccak_hasher::hash(&bytes).to_bytes();
+
+    let mut stack = run_keccak(&[Felt::ONE, Felt::TWO], KeccakHash::new(2));
+    assert_eq!(KeccakHash::pop_result(&mut stack), masked_160(&expected));
+}
+
+#[test]
+fn hash_node_matches_reference() {
+    let (x, y) = (Felt::from_hex_unchecked("0x1234"), Felt::MAX);
+
+    let reference = starknet_crypto::poseidon_hash(
+        starknet_types_core::felt::Felt::from_bytes_be(&x.to_bytes_be()),
+        starknet_types_core::felt::Felt::from_bytes_be(&y.to_bytes_be()),
+    );
+    assert_eq!(
+        hash_node(x, y, true),
+        Felt::from_bytes_be(&reference.to_bytes_be())
+    );
+
+    assert_eq!(
+        hash_node(x, y, false),
+        masked_160(&keccak_felts(&[x, y], FeltEncoding::BigEndian))
+    );
+}
@@ -1,7 +1,18 @@
-use stark::{felt::Felt, pedersen::PedersenHash};
+mod common;
+
+use stark::{
+    felt::Felt,
+    pedersen::{
+        constants::{POINTS_P1, POINTS_P2, POINTS_P3, POINTS_P4},
+        PedersenHash, PedersenTables, WindowTable, HIGH_BITS, LOOKUP_COMPUTE_UNITS, LOW_BITS,
+        PEDERSEN_TABLES_ID, POINT_BYTES,
+    },
+};
 use swiftness_proof_parser::{json_parser, transform::TransformTo, StarkProof as StarkProofParser};
-use utils::{BidirectionalStack, Scheduler};
-use verifier::state::BidirectionalStackAccount;
+use utils::{constants::push_table, BidirectionalStack, Executable, Reject, Scheduler};
+use verifier::state::{BidirectionalStackAccount, VerificationState};
+
+use common::run_task;
 
 #[test]
 fn pedersen_hash() {
@@ -28,9 +39,9 @@ fn pedersen_hash() {
     );
     stack.push_task(PedersenHash::new());
     while !stack.is_empty_back() {
-        stack.execute();
+        stack.execute().unwrap();
     }
-    let result = Felt::from_bytes_be_slice(stack.borrow_front().try_into().unwrap());
+    let result = Felt::from_bytes_be_slice(stack.borrow_front());
     println!("result: {:?}", result);
 
     let expected = Felt::from_hex_unchecked(
@@ -48,3 +59,152 @@ fn pedersen_hash() {
     assert_eq!(stack.front_index, 0);
     assert_eq!(stack.back_index, 65536);
 }
+
+#[test]
+fn pedersen_hash_without_inputs() {
+    let mut stack = BidirectionalStackAccount::default();
+    stack.push_front(&Felt::ONE.to_bytes_be()).unwrap();
+
+    stack.push_task(PedersenHash::new());
+    stack.execute().unwrap();
+
+    // The task is rejected in place of running
+    assert_eq!(stack.status.state().unwrap(), VerificationState::Rejected);
+    assert_eq!(stack.status.error_code, Reject::MISSING_FRAME_INPUTS);
+    assert_eq!(stack.status.failed_task, PedersenHash::TYPE_TAG);
+    assert!(stack.is_empty_back());
+    assert_eq!(stack.executed_steps, 1);
+}
+
+/// Hash with `task` and the tables in `constants`, if any, returning the hash
+/// and the number of steps
+fn run_pedersen(x: Felt, y: Felt, task: PedersenHash, constants: &[u8]) -> (Felt, usize) {
+    let (mut stack, steps) = run_task(
+        |stack| PedersenHash::push_input(x, y, stack),
+        task,
+        constants,
+    );
+    let result = PedersenHash::pop_result(&mut stack);
+    assert_eq!(stack.front_index, 0);
+    (result, steps)
+}
+
+fn reference_pedersen(x: Felt, y: Felt) -> Felt {
+    let x = starknet_types_core::felt::Felt::from_bytes_be(&x.to_bytes_be());
+    let y = starknet_types_core::felt::Felt::from_bytes_be(&y.to_bytes_be());
+    Felt::from_bytes_be(&starknet_crypto::pedersen_hash(&x, &y).to_bytes_be())
+}
+
+#[test]
+fn pedersen_hash_matches_reference() {
+    let inputs = [
+        (Felt::ZERO, Felt::ZERO),
+        (Felt::ONE, Felt::TWO),
+        (Felt::MAX, Felt::MAX),
+        (Felt::MAX, Felt::ZERO),
+        (
+            Felt::from_hex_unchecked(
+                "0x7f8e1d2c3b4a59687766554433221100ffeeddccbbaa99887766554433221",
+            ),
+            Felt::from_hex_unchecked(
+                "0x800000000000011000000000000000000000000000000000000000000000000",
+            ),
+        ),
+    ];
+    // The built-in tables, then the 8-bit tables `deploy` stores
+    let wide_bytes = PedersenTables::generate_bytes(8);
+    let wide_tables = PedersenTables::from_bytes(&wide_bytes).unwrap();
+    for high in [wide_tables.0[1], wide_tables.0[3]] {
+        assert_eq!(high.window_bits, HIGH_BITS);
+        assert_eq!(high.points.len(), ((1 << HIGH_BITS) - 1) * POINT_BYTES);
+    }
+    let mut wide_constants = Vec::new();
+    push_table(&mut wide_constants, PEDERSEN_TABLES_ID, &wide_bytes);
+    let tables = [
+        (PedersenTables::builtin(), &[][..]),
+        (wide_tables, &wide_constants[..]),
+    ];
+
+    for (tables, constants) in tables {
+        let lookups = tables.lookups();
+        for (x, y) in inputs {
+            let expected = reference_pedersen(x, y);
+            // One lookup per step, a few per step, and all of them in one step
+            for compute_units in [0, 7 * LOOKUP_COMPUTE_UNITS, u64::MAX] {
+                let task = PedersenHash::with_budget(compute_units);
+                let (result, steps) = run_pedersen(x, y, task, constants);
+                assert_eq!(
+                    result, expected,
+                    "pedersen({x}, {y}), {compute_units} units, {lookups} lookups"
+                );
+                assert!(steps <= lookups);
+            }
+        }
+    }
+
+    let (_, steps) = run_pedersen(Felt::ONE, Felt::TWO, PedersenHash::new(), &[]);
+    assert!(steps <= 3, "{steps} steps");
+}
+
+#[test]
+fn pedersen_tables_match_generated() {
+    let tables = [
+        (&POINTS_P1[..], LOW_BITS),
+        (&POINTS_P2[..], HIGH_BITS),
+        (&POINTS_P3[..], LOW_BITS),
+        (&POINTS_P4[..], HIGH_BITS),
+    ];
+    for (points, scalar_bits) in tables {
+        let generated =
+            WindowTable::generate(&points[0], PedersenHash::CURVE_CONST_BITS, scalar_bits);
+        assert_eq!(generated, points);
+    }
+
+    // The first point of the first table, right after the window width
+    let bytes = PedersenTables::generate_bytes(PedersenHash::CURVE_CONST_BITS);
+    let x = Felt::from_hex_unchecked(
+        "0x0234287dcbaffe7f969c748655fca9e58fa8120b6d56eb0c1080d17957ebe47b",
+    );
+    let y = Felt::from_hex_unchecked(
+        "0x03b056f100f96fb21e889527d41f4e39940135dd7a6c94cc6ed0268ee89e5615",
+    );
+    let encoded: Vec<u8> = [x, y]
+        .iter()
+        .flat_map(Felt::to_raw_reversed)
+        .flat_map(u64::to_le_bytes)
+        .collect();
+    assert_eq!(&bytes[8..8 + encoded.len()], encoded);
+    let tables = PedersenTables::from_bytes(&bytes).unwrap();
+    assert_eq!(tables.0[0].lookup(0, 1), Some((x, y)));
+    assert_eq!(PedersenTables::builtin().0[0].lookup(0, 1), Some((x, y)));
+}
+
+#[test]
+fn pedersen_hash_with_constants() {
+    let (x, y) = (
+        Felt::from_hex_unchecked(
+            "0x3d937c035c878245caf64531a5756109c53068da139362728feb561405371cb",
+        ),
+        Felt::MAX,
+    );
+    let expected = reference_pedersen(x, y);
+    let (_, builtin_steps) = run_pedersen(x, y, PedersenHash::with_budget(0), &[]);
+
+    // 6 bits do not divide the low bits, the last window of those tables is
+    // narrower
+    for window_bits in [PedersenHash::CURVE_CONST_BITS, 6] {
+        let bytes = PedersenTables::generate_bytes(window_bits);
+        let tables = PedersenTables::from_bytes(&bytes).unwrap();
+        assert_eq!(tables.0[0].window_bits, window_bits);
+        assert!(PedersenTables::from_bytes(&bytes[..bytes.len() - 8]).is_none());
+
+        let mut constants = Vec::new();
+        push_table(&mut constants, PEDERSEN_TABLES_ID, &bytes);
+        let (result, steps) = run_pedersen(x, y, PedersenHash::with_budget(0), &constants);
+        assert_eq!(result, expected, "{window_bits}-bit windows");
+        assert_eq!(steps, tables.lookups());
+        if window_bits > PedersenHash::CURVE_CONST_BITS {
+            assert!(steps < builtin_steps);
+        }
+    }
+}
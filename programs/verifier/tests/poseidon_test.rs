@@ -1,8 +1,16 @@
+mod common;
+
 use stark::felt::Felt;
-use stark::poseidon::PoseidonHashMany;
+use stark::poseidon::hades::HadesPermutation;
+use stark::poseidon::{PoseidonHashMainPage, PoseidonHashMany};
+use stark::swiftness::stark::types::StarkProof;
+use stark::HASH_STEP_COMPUTE_UNITS;
+use swiftness_proof_parser::{json_parser, transform::TransformTo, StarkProof as StarkProofParser};
 use utils::{BidirectionalStack, Scheduler};
 use verifier::state::BidirectionalStackAccount;
 
+use common::run_task;
+
 #[test]
 fn test_poseidon_hash_1() {
     let a = Felt::from_hex("0x1").unwrap();
@@ -61,7 +69,7 @@ fn test_hash_with_inputs(inputs: &[Felt], expected: Felt) {
     // Execute until completion
     let mut steps = 0;
     while !stack.is_empty_back() {
-        stack.execute();
+        stack.execute().unwrap();
         steps += 1;
     }
 
@@ -77,3 +85,79 @@ fn test_hash_with_inputs(inputs: &[Felt], expected: Felt) {
     assert!(steps > 0, "Should have executed at least one step");
     assert_eq!(stack.front_index, 0, "Stack should be empty after test");
 }
+
+#[test]
+fn test_poseidon_hash_with_budget() {
+    let inputs: Vec<Felt> = (1..=9u64).map(Felt::from).collect();
+
+    for len in 0..=inputs.len() {
+        let inputs = &inputs[..len];
+        let (expected, steps) = run_hash(inputs, PoseidonHashMany::new(inputs.len()));
+
+        // One round per step, then several rounds in a single step
+        for compute_units in [0, 2 * HadesPermutation::COMPUTE_UNITS, u64::MAX] {
+            let task = PoseidonHashMany::with_budget(inputs.len(), compute_units);
+            let (result, batched_steps) = run_hash(inputs, task);
+            assert_eq!(result, expected, "{len} inputs, {compute_units} units");
+            assert!(batched_steps < steps);
+        }
+    }
+}
+
+/// Hash `inputs` with `task`, returning the hash and the number of steps
+fn run_hash(inputs: &[Felt], task: PoseidonHashMany) -> (Felt, usize) {
+    let (mut stack, steps) = run_task(
+        |stack| PoseidonHashMany::push_input(inputs, stack),
+        task,
+        &[],
+    );
+    let result = PoseidonHashMany::pop_result(&mut stack);
+    assert_eq!(stack.front_index, 0, "Stack should be empty after the hash");
+    (result, steps)
+}
+
+#[test]
+fn test_poseidon_hash_main_page() {
+    let mut stack = BidirectionalStackAccount::default();
+
+    let input = include_str!("../../../example_proof/saya.json");
+    let proof_json = serde_json::from_str::<json_parser::StarkProof>(input).unwrap();
+    let proof = StarkProofParser::try_from(proof_json).unwrap();
+    let proof_verifier: StarkProof = proof.transform_to();
+    let main_page: Vec<Felt> = proof_verifier
+        .public_input
+        .main_page
+        .0
+        .as_slice()
+        .iter()
+        .map(|cell| cell.value)
+        .collect();
+
+    stack.proof = proof_verifier;
+
+    let end = main_page.len();
+    for (start, len) in [(0, 0), (0, 1), (0, 6), (3, 5), (end - 7, 7)] {
+        // Hashing the same values staged on the front stack
+        PoseidonHashMany::push_input(&main_page[start..start + len], &mut stack);
+        stack.push_task(PoseidonHashMany::new(len));
+        while !stack.is_empty_back() {
+            stack.execute().unwrap();
+        }
+        let expected = PoseidonHashMany::pop_result(&mut stack);
+
+        let tasks = [
+            PoseidonHashMainPage::new(start, len),
+            PoseidonHashMainPage::with_budget(start, len, HASH_STEP_COMPUTE_UNITS),
+        ];
+        for task in tasks {
+            stack.push_task(task);
+            while !stack.is_empty_back() {
+                stack.execute().unwrap();
+            }
+            let result = PoseidonHashMany::pop_result(&mut stack);
+
+            assert_eq!(result, expected, "cells {start}..{}", start + len);
+            assert_eq!(stack.front_index, 0, "Stack should be empty after the hash");
+        }
+    }
+}
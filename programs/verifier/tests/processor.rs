@@ -0,0 +1,789 @@
+use std::mem::offset_of;
+
+use arithmetic::add::Add;
+use solana_loader_v3_interface::{get_program_data_address, state::UpgradeableLoaderState};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use stark::felt::Felt;
+use stark::pedersen::PedersenHash;
+use stark::stark_proof::VerifyPublicInput;
+use stark::swiftness::stark::types::cast_struct_to_slice;
+use swiftness_proof_parser::{json_parser, transform::TransformTo, StarkProof as StarkProofParser};
+use utils::{AccountCast, BidirectionalStack, Scheduler};
+use verifier::{
+    error::VerifierError,
+    instruction::VerifierInstruction,
+    state::{
+        BidirectionalStackAccount, ConstantsAccount, VerificationState, ACCOUNT_VERSION,
+        CONSTANTS_HEADER,
+    },
+};
+
+/// Verifier program, run natively
+fn program_test(program_id: Pubkey) -> ProgramTest {
+    ProgramTest::new(
+        "verifier",
+        program_id,
+        processor!(verifier::processor::process_instruction),
+    )
+}
+
+/// Add a rent-exempt account holding `data` and owned by `owner`
+fn add_account(program_test: &mut ProgramTest, address: Pubkey, owner: Pubkey, data: Vec<u8>) {
+    program_test.add_account(
+        address,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
+
+/// Start the verifier program, run natively, with `accounts` owned by it
+async fn start(program_id: Pubkey, accounts: &[(Pubkey, Vec<u8>)]) -> ProgramTestContext {
+    let mut program_test = program_test(program_id);
+    for (address, data) in accounts {
+        add_account(&mut program_test, *address, program_id, data.clone());
+    }
+    program_test.start_with_context().await
+}
+
+/// Program data account of `program_id` recording `upgrade_authority`, to be
+/// added to the program test
+fn program_data_account(program_id: &Pubkey, upgrade_authority: Pubkey) -> (Pubkey, Account) {
+    let state = UpgradeableLoaderState::ProgramData {
+        slot: 0,
+        upgrade_authority_address: Some(upgrade_authority),
+    };
+    let data = bincode::serialize(&state).unwrap();
+    let account = Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: solana_sdk_ids::bpf_loader_upgradeable::id(),
+        executable: false,
+        rent_epoch: 0,
+    };
+    (get_program_data_address(program_id), account)
+}
+
+/// Process `instruction` in a transaction paid by the context payer
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+/// Assert that the transaction failed with `error`
+fn assert_error(result: Result<(), BanksClientError>, error: impl Into<ProgramError>) {
+    let expected = error.into();
+    match result {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            instruction_error,
+        ))) => assert_eq!(ProgramError::try_from(instruction_error), Ok(expected)),
+        result => panic!("Expected {expected:?}, got {result:?}"),
+    }
+}
+
+/// Data of a stack account
+fn account_data(stack: &BidirectionalStackAccount) -> Vec<u8> {
+    cast_struct_to_slice(stack).to_vec()
+}
+
+/// Data of a frozen constants account holding `constants`
+fn frozen_constants_data(constants: &[u8]) -> Vec<u8> {
+    let mut data = vec![0u8; size_of::<ConstantsAccount>()];
+    data[..CONSTANTS_HEADER.len()].copy_from_slice(&CONSTANTS_HEADER);
+    ConstantsAccount::split_mut(&mut data).unwrap().0.frozen = 1;
+    data.extend_from_slice(constants);
+    data
+}
+
+/// Stack account holding the saya proof, with `authority`
+fn saya_stack(authority: &Keypair) -> Box<BidirectionalStackAccount> {
+    let mut stack = Box::<BidirectionalStackAccount>::default();
+
+    let input = include_str!("../../../example_proof/saya.json");
+    let proof_json = serde_json::from_str::<json_parser::StarkProof>(input).unwrap();
+    let proof = StarkProofParser::try_from(proof_json).unwrap();
+    stack.proof = proof.transform_to();
+    stack.authority = authority.pubkey();
+    stack
+}
+
+fn reset_instruction(
+    program_id: Pubkey,
+    account: Pubkey,
+    authority: Pubkey,
+    clear_proof: bool,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        program_id,
+        &VerifierInstruction::Reset(clear_proof),
+        vec![
+            AccountMeta::new(account, false),
+            AccountMeta::new_readonly(authority, true),
+        ],
+    )
+}
+
+fn close_instruction(
+    program_id: Pubkey,
+    account: Pubkey,
+    authority: Pubkey,
+    recipient: Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        program_id,
+        &VerifierInstruction::Close,
+        vec![
+            AccountMeta::new(account, false),
+            AccountMeta::new_readonly(authority, true),
+            AccountMeta::new(recipient, false),
+        ],
+    )
+}
+
+fn set_account_data_instruction(
+    program_id: Pubkey,
+    account: Pubkey,
+    authority: Pubkey,
+    offset: usize,
+    data: Vec<u8>,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        program_id,
+        &VerifierInstruction::SetAccountData(offset, data),
+        vec![
+            AccountMeta::new(account, false),
+            AccountMeta::new_readonly(authority, true),
+        ],
+    )
+}
+
+fn execute_instruction(
+    program_id: Pubkey,
+    account: Pubkey,
+    constants_account: Option<Pubkey>,
+    step: u32,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(account, false)];
+    accounts.extend(constants_account.map(|account| AccountMeta::new_readonly(account, false)));
+    Instruction::new_with_borsh(program_id, &VerifierInstruction::Execute(step), accounts)
+}
+
+fn initialize_constants_instruction(
+    program_id: Pubkey,
+    account: Pubkey,
+    authority: Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        program_id,
+        &VerifierInstruction::InitializeConstants,
+        vec![
+            AccountMeta::new(account, false),
+            AccountMeta::new_readonly(authority, true),
+            AccountMeta::new_readonly(get_program_data_address(&program_id), false),
+        ],
+    )
+}
+
+fn set_constants_data_instruction(
+    program_id: Pubkey,
+    account: Pubkey,
+    authority: Pubkey,
+    offset: usize,
+    data: Vec<u8>,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        program_id,
+        &VerifierInstruction::SetConstantsData(offset, data),
+        vec![
+            AccountMeta::new(account, false),
+            AccountMeta::new_readonly(authority, true),
+        ],
+    )
+}
+
+fn freeze_constants_instruction(
+    program_id: Pubkey,
+    account: Pubkey,
+    authority: Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        program_id,
+        &VerifierInstruction::FreezeConstants,
+        vec![
+            AccountMeta::new(account, false),
+            AccountMeta::new_readonly(authority, true),
+        ],
+    )
+}
+
+async fn stack_account(context: &mut ProgramTestContext, address: Pubkey) -> Vec<u8> {
+    let account = context.banks_client.get_account(address).await.unwrap();
+    account.expect("account exists").data
+}
+
+#[tokio::test]
+async fn public_input_run_is_reset_and_closed() {
+    // A successful `VerifyPublicInput` run completes, it is no proof fact
+    let authority = Keypair::new();
+    let mut stack = saya_stack(&authority);
+    stack.push_task(VerifyPublicInput::new());
+    while !stack.is_empty_back() {
+        stack.execute().unwrap();
+    }
+    assert_eq!(stack.status.state().unwrap(), VerificationState::Completed);
+    let data = account_data(&stack);
+
+    let program_id = Pubkey::new_unique();
+    let (reset_account, closed_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut context = start(
+        program_id,
+        &[(reset_account, data.clone()), (closed_account, data)],
+    )
+    .await;
+
+    let reset = reset_instruction(program_id, reset_account, authority.pubkey(), false);
+    process(&mut context, reset, &[&authority]).await.unwrap();
+    let data = stack_account(&mut context, reset_account).await;
+    let stack = BidirectionalStackAccount::cast(&data);
+    assert_eq!(stack.status.state().unwrap(), VerificationState::Pending);
+    assert!(stack.is_empty_front());
+
+    let recipient = Pubkey::new_unique();
+    let close = close_instruction(program_id, closed_account, authority.pubkey(), recipient);
+    process(&mut context, close, &[&authority]).await.unwrap();
+    let closed = context.banks_client.get_account(closed_account).await;
+    assert_eq!(closed.unwrap(), None);
+}
+
+/// Stack account of `authority` about to add one and two
+fn add_stack(authority: &Keypair) -> Box<BidirectionalStackAccount> {
+    let mut stack = Box::<BidirectionalStackAccount>::default();
+    stack.authority = authority.pubkey();
+    stack.push_data(&Felt::ONE.to_bytes_be());
+    stack.push_data(&Felt::TWO.to_bytes_be());
+    stack.push_task(Add::new(1, 2));
+    stack
+}
+
+#[tokio::test]
+async fn execute_pins_the_constants_account() {
+    let stack = add_stack(&Keypair::new());
+
+    let program_id = Pubkey::new_unique();
+    let (account, constants_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut context = start(
+        program_id,
+        &[
+            (account, account_data(&stack)),
+            (constants_account, frozen_constants_data(&[])),
+        ],
+    )
+    .await;
+
+    let execute = execute_instruction(program_id, account, Some(constants_account), 0);
+    process(&mut context, execute, &[]).await.unwrap();
+    let data = stack_account(&mut context, account).await;
+    let stack = BidirectionalStackAccount::cast(&data);
+    assert_eq!(stack.constants_account, constants_account);
+}
+
+#[tokio::test]
+async fn execute_refuses_another_constants_account() {
+    let mut stack = add_stack(&Keypair::new());
+    stack.constants_account = Pubkey::new_unique();
+
+    let program_id = Pubkey::new_unique();
+    let (account, constants_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut context = start(
+        program_id,
+        &[
+            (account, account_data(&stack)),
+            (constants_account, frozen_constants_data(&[])),
+        ],
+    )
+    .await;
+
+    let execute = execute_instruction(program_id, account, Some(constants_account), 0);
+    let result = process(&mut context, execute, &[]).await;
+    assert_error(result, VerifierError::ConstantsMismatch);
+}
+
+#[tokio::test]
+async fn execute_without_constants_refuses_a_pedersen_hash() {
+    let mut stack = Box::<BidirectionalStackAccount>::default();
+    PedersenHash::push_input(Felt::ONE, Felt::TWO, &mut *stack);
+    stack.push_task(PedersenHash::new());
+
+    let program_id = Pubkey::new_unique();
+    let account = Pubkey::new_unique();
+    let mut context = start(program_id, &[(account, account_data(&stack))]).await;
+
+    let execute = execute_instruction(program_id, account, None, 0);
+    let result = process(&mut context, execute, &[]).await;
+    assert_error(result, VerifierError::MissingConstants);
+}
+
+#[tokio::test]
+async fn execute_without_tasks_fails() {
+    let stack = Box::<BidirectionalStackAccount>::default();
+
+    let program_id = Pubkey::new_unique();
+    let account = Pubkey::new_unique();
+    let mut context = start(program_id, &[(account, account_data(&stack))]).await;
+
+    let execute = execute_instruction(program_id, account, None, 0);
+    let result = process(&mut context, execute, &[]).await;
+    assert_error(result, VerifierError::EmptyStack);
+}
+
+#[tokio::test]
+async fn execute_refuses_unexpected_steps() {
+    // A second addition keeps the verification running after the first step
+    let mut stack = add_stack(&Keypair::new());
+    stack.push_data(&Felt::ONE.to_bytes_be());
+    stack.push_data(&Felt::TWO.to_bytes_be());
+    stack.push_task(Add::new(1, 2));
+
+    let program_id = Pubkey::new_unique();
+    let account = Pubkey::new_unique();
+    let mut context = start(program_id, &[(account, account_data(&stack))]).await;
+
+    // A skipped step
+    let execute = execute_instruction(program_id, account, None, 1);
+    let result = process(&mut context, execute, &[]).await;
+    assert_error(result, VerifierError::UnexpectedStep);
+
+    let execute = execute_instruction(program_id, account, None, 0);
+    process(&mut context, execute.clone(), &[]).await.unwrap();
+
+    // A replayed step, in a new transaction
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let result = process(&mut context, execute, &[]).await;
+    assert_error(result, VerifierError::UnexpectedStep);
+
+    let data = stack_account(&mut context, account).await;
+    assert_eq!(BidirectionalStackAccount::cast(&data).executed_steps, 1);
+}
+
+#[tokio::test]
+async fn set_account_data_requires_the_authority() {
+    let authority = Keypair::new();
+    let stack = add_stack(&authority);
+
+    let program_id = Pubkey::new_unique();
+    let account = Pubkey::new_unique();
+    let mut context = start(program_id, &[(account, account_data(&stack))]).await;
+
+    let proof_offset = offset_of!(BidirectionalStackAccount, proof);
+    let other = Keypair::new();
+    let set_data =
+        set_account_data_instruction(program_id, account, other.pubkey(), proof_offset, vec![1]);
+    let result = process(&mut context, set_data, &[&other]).await;
+    assert_error(result, VerifierError::Unauthorized);
+
+    let set_data = set_account_data_instruction(
+        program_id,
+        account,
+        authority.pubkey(),
+        proof_offset,
+        vec![1, 2],
+    );
+    process(&mut context, set_data, &[&authority])
+        .await
+        .unwrap();
+    let data = stack_account(&mut context, account).await;
+    assert_eq!(data[proof_offset..proof_offset + 2], [1, 2]);
+}
+
+#[tokio::test]
+async fn set_account_data_refuses_protected_regions() {
+    let authority = Keypair::new();
+    let stack = add_stack(&authority);
+
+    let program_id = Pubkey::new_unique();
+    let account = Pubkey::new_unique();
+    let data = account_data(&stack);
+    let data_len = data.len();
+    let mut context = start(program_id, &[(account, data)]).await;
+
+    // Writes overlapping the header, the status, the authority or the
+    // constants account, by a single byte
+    let status = offset_of!(BidirectionalStackAccount, status);
+    let constants_account = offset_of!(BidirectionalStackAccount, constants_account);
+    for (offset, len) in [
+        (0, 1),
+        (status - 1, 2),
+        (offset_of!(BidirectionalStackAccount, authority), 1),
+        (constants_account + size_of::<Pubkey>() - 1, 1),
+    ] {
+        let set_data = set_account_data_instruction(
+            program_id,
+            account,
+            authority.pubkey(),
+            offset,
+            vec![1; len],
+        );
+        let result = process(&mut context, set_data, &[&authority]).await;
+        assert_error(result, VerifierError::ProtectedRegion);
+    }
+
+    // Writes past the end of the account
+    for offset in [data_len - 1, usize::MAX] {
+        let set_data = set_account_data_instruction(
+            program_id,
+            account,
+            authority.pubkey(),
+            offset,
+            vec![1; 2],
+        );
+        let result = process(&mut context, set_data, &[&authority]).await;
+        assert_error(result, ProgramError::AccountDataTooSmall);
+    }
+
+    // Right next to the protected regions
+    for offset in [status - 1, constants_account + size_of::<Pubkey>()] {
+        let set_data =
+            set_account_data_instruction(program_id, account, authority.pubkey(), offset, vec![0]);
+        process(&mut context, set_data, &[&authority])
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn close_drains_all_lamports_to_the_recipient() {
+    let authority = Keypair::new();
+    let stack = add_stack(&authority);
+
+    let program_id = Pubkey::new_unique();
+    let account = Pubkey::new_unique();
+    let mut context = start(program_id, &[(account, account_data(&stack))]).await;
+    let lamports = context.banks_client.get_balance(account).await.unwrap();
+
+    // Only the authority closes the account, and not to itself
+    let other = Keypair::new();
+    let recipient = Pubkey::new_unique();
+    let close = close_instruction(program_id, account, other.pubkey(), recipient);
+    let result = process(&mut context, close, &[&other]).await;
+    assert_error(result, VerifierError::Unauthorized);
+    let close = close_instruction(program_id, account, authority.pubkey(), account);
+    let result = process(&mut context, close, &[&authority]).await;
+    assert_error(result, ProgramError::InvalidArgument);
+
+    let close = close_instruction(program_id, account, authority.pubkey(), recipient);
+    process(&mut context, close, &[&authority]).await.unwrap();
+    let closed = context.banks_client.get_account(account).await.unwrap();
+    assert_eq!(closed, None);
+    let received = context.banks_client.get_balance(recipient).await.unwrap();
+    assert_eq!(received, lamports);
+}
+
+#[tokio::test]
+async fn verified_account_is_neither_closed_nor_reset() {
+    let authority = Keypair::new();
+    let mut stack = add_stack(&authority);
+    stack.status.set_state(VerificationState::Verified);
+
+    let program_id = Pubkey::new_unique();
+    let account = Pubkey::new_unique();
+    let mut context = start(program_id, &[(account, account_data(&stack))]).await;
+
+    let close = close_instruction(
+        program_id,
+        account,
+        authority.pubkey(),
+        Pubkey::new_unique(),
+    );
+    let result = process(&mut context, close, &[&authority]).await;
+    assert_error(result, VerifierError::VerifiedFact);
+
+    for clear_proof in [false, true] {
+        let reset = reset_instruction(program_id, account, authority.pubkey(), clear_proof);
+        let result = process(&mut context, reset, &[&authority]).await;
+        assert_error(result, VerifierError::VerifiedFact);
+    }
+
+    let data = stack_account(&mut context, account).await;
+    let stack = BidirectionalStackAccount::cast(&data);
+    assert_eq!(stack.status.state().unwrap(), VerificationState::Verified);
+}
+
+#[tokio::test]
+async fn reset_clears_the_status_and_counters() {
+    let authority = Keypair::new();
+    let mut stack = add_stack(&authority);
+    stack.executed_steps = 3;
+    stack.status.reject(1, 2);
+    stack.constants_account = Pubkey::new_unique();
+    stack.proof.public_input.layout = Felt::ONE;
+
+    let program_id = Pubkey::new_unique();
+    let account = Pubkey::new_unique();
+    let mut context = start(program_id, &[(account, account_data(&stack))]).await;
+
+    let reset = reset_instruction(program_id, account, authority.pubkey(), false);
+    process(&mut context, reset, &[&authority]).await.unwrap();
+    let data = stack_account(&mut context, account).await;
+    let stack = BidirectionalStackAccount::cast(&data);
+    assert_eq!(stack.status.state().unwrap(), VerificationState::Pending);
+    assert_eq!(stack.status.error_code, 0);
+    assert_eq!(stack.status.failed_task, 0);
+    assert_eq!(stack.executed_steps, 0);
+    assert!(stack.is_empty_front());
+    assert!(stack.is_empty_back());
+    assert_eq!(stack.constants_account, Pubkey::default());
+    assert_eq!(stack.authority, authority.pubkey());
+    assert_eq!(stack.proof.public_input.layout, Felt::ONE);
+
+    let reset = reset_instruction(program_id, account, authority.pubkey(), true);
+    process(&mut context, reset, &[&authority]).await.unwrap();
+    let data = stack_account(&mut context, account).await;
+    let stack = BidirectionalStackAccount::cast(&data);
+    assert_eq!(stack.proof.public_input.layout, Felt::ZERO);
+}
+
+/// Data of a stack account written with the previous layout version
+fn old_version_data(authority: &Keypair) -> Vec<u8> {
+    let mut data = account_data(&add_stack(authority));
+    data[4..8].copy_from_slice(&(ACCOUNT_VERSION - 1).to_le_bytes());
+    data
+}
+
+#[tokio::test]
+async fn old_version_account_is_refused() {
+    let authority = Keypair::new();
+
+    let program_id = Pubkey::new_unique();
+    let account = Pubkey::new_unique();
+    let mut context = start(program_id, &[(account, old_version_data(&authority))]).await;
+
+    let execute = execute_instruction(program_id, account, None, 0);
+    let result = process(&mut context, execute, &[]).await;
+    assert_error(result, VerifierError::UnsupportedAccountVersion);
+
+    let instructions = [
+        reset_instruction(program_id, account, authority.pubkey(), false),
+        set_account_data_instruction(program_id, account, authority.pubkey(), 200, vec![1]),
+    ];
+    for instruction in instructions {
+        let result = process(&mut context, instruction, &[&authority]).await;
+        assert_error(result, VerifierError::UnsupportedAccountVersion);
+    }
+}
+
+#[tokio::test]
+async fn upgrade_authority_closes_an_old_version_account() {
+    let authority = Keypair::new();
+    let upgrade_authority = Keypair::new();
+
+    let program_id = Pubkey::new_unique();
+    let account = Pubkey::new_unique();
+    let mut program_test = program_test(program_id);
+    let (program_data, program_data_account) =
+        program_data_account(&program_id, upgrade_authority.pubkey());
+    program_test.add_account(program_data, program_data_account);
+    add_account(
+        &mut program_test,
+        account,
+        program_id,
+        old_version_data(&authority),
+    );
+    let mut context = program_test.start_with_context().await;
+
+    // The authority recorded in the account no longer counts
+    let recipient = Pubkey::new_unique();
+    for signer in [&authority, &upgrade_authority] {
+        let close = close_instruction(program_id, account, signer.pubkey(), recipient);
+        let result = process(&mut context, close, &[signer]).await;
+        assert_error(result, ProgramError::NotEnoughAccountKeys);
+    }
+    let mut close = close_instruction(program_id, account, authority.pubkey(), recipient);
+    close
+        .accounts
+        .push(AccountMeta::new_readonly(program_data, false));
+    let result = process(&mut context, close, &[&authority]).await;
+    assert_error(result, VerifierError::Unauthorized);
+
+    let mut close = close_instruction(program_id, account, upgrade_authority.pubkey(), recipient);
+    close
+        .accounts
+        .push(AccountMeta::new_readonly(program_data, false));
+    process(&mut context, close, &[&upgrade_authority])
+        .await
+        .unwrap();
+    let closed = context.banks_client.get_account(account).await.unwrap();
+    assert_eq!(closed, None);
+}
+
+#[tokio::test]
+async fn constants_account_is_initialized_written_and_frozen() {
+    let upgrade_authority = Keypair::new();
+    let other = Keypair::new();
+
+    let program_id = Pubkey::new_unique();
+    let (account, constants_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut program_test = program_test(program_id);
+    let (program_data, program_data_account) =
+        program_data_account(&program_id, upgrade_authority.pubkey());
+    program_test.add_account(program_data, program_data_account);
+    add_account(
+        &mut program_test,
+        account,
+        program_id,
+        account_data(&add_stack(&Keypair::new())),
+    );
+    let constants_len = 64;
+    add_account(
+        &mut program_test,
+        constants_account,
+        program_id,
+        vec![0; size_of::<ConstantsAccount>() + constants_len],
+    );
+    let mut context = program_test.start_with_context().await;
+
+    // Only the upgrade authority initializes, and only once
+    let initialize =
+        initialize_constants_instruction(program_id, constants_account, other.pubkey());
+    let result = process(&mut context, initialize, &[&other]).await;
+    assert_error(result, VerifierError::Unauthorized);
+    let initialize =
+        initialize_constants_instruction(program_id, constants_account, upgrade_authority.pubkey());
+    process(&mut context, initialize.clone(), &[&upgrade_authority])
+        .await
+        .unwrap();
+    context.get_new_latest_blockhash().await.unwrap();
+    let result = process(&mut context, initialize, &[&upgrade_authority]).await;
+    assert_error(result, VerifierError::AlreadyInitialized);
+
+    // Writes need the authority and must stay within the constants region
+    let write = set_constants_data_instruction(
+        program_id,
+        constants_account,
+        other.pubkey(),
+        0,
+        vec![1; 8],
+    );
+    let result = process(&mut context, write, &[&other]).await;
+    assert_error(result, VerifierError::Unauthorized);
+    for offset in [constants_len - 1, usize::MAX] {
+        let write = set_constants_data_instruction(
+            program_id,
+            constants_account,
+            upgrade_authority.pubkey(),
+            offset,
+            vec![1; 2],
+        );
+        let result = process(&mut context, write, &[&upgrade_authority]).await;
+        assert_error(result, ProgramError::AccountDataTooSmall);
+    }
+    let write = set_constants_data_instruction(
+        program_id,
+        constants_account,
+        upgrade_authority.pubkey(),
+        constants_len - 8,
+        vec![1; 8],
+    );
+    process(&mut context, write, &[&upgrade_authority])
+        .await
+        .unwrap();
+    let data = stack_account(&mut context, constants_account).await;
+    let (_, constants) = ConstantsAccount::split(&data).unwrap();
+    assert_eq!(constants[constants_len - 8..], [1; 8]);
+
+    // Execute only reads frozen constants
+    let execute = execute_instruction(program_id, account, Some(constants_account), 0);
+    let result = process(&mut context, execute.clone(), &[]).await;
+    assert_error(result, VerifierError::ConstantsNotFrozen);
+
+    let freeze =
+        freeze_constants_instruction(program_id, constants_account, upgrade_authority.pubkey());
+    process(&mut context, freeze.clone(), &[&upgrade_authority])
+        .await
+        .unwrap();
+    context.get_new_latest_blockhash().await.unwrap();
+    process(&mut context, execute, &[]).await.unwrap();
+
+    // Once frozen, the constants no longer change
+    context.get_new_latest_blockhash().await.unwrap();
+    let result = process(&mut context, freeze, &[&upgrade_authority]).await;
+    assert_error(result, VerifierError::ConstantsFrozen);
+    let write = set_constants_data_instruction(
+        program_id,
+        constants_account,
+        upgrade_authority.pubkey(),
+        0,
+        vec![2; 8],
+    );
+    let result = process(&mut context, write, &[&upgrade_authority]).await;
+    assert_error(result, VerifierError::ConstantsFrozen);
+}
+
+#[tokio::test]
+async fn execute_refuses_constants_of_another_owner() {
+    let program_id = Pubkey::new_unique();
+    let (account, constants_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut program_test = program_test(program_id);
+    add_account(
+        &mut program_test,
+        account,
+        program_id,
+        account_data(&add_stack(&Keypair::new())),
+    );
+    add_account(
+        &mut program_test,
+        constants_account,
+        Pubkey::new_unique(),
+        frozen_constants_data(&[]),
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let execute = execute_instruction(program_id, account, Some(constants_account), 0);
+    let result = process(&mut context, execute, &[]).await;
+    assert_error(result, VerifierError::InvalidOwner);
+}
+
+#[tokio::test]
+async fn execute_refuses_a_stack_account_as_constants() {
+    let stack = add_stack(&Keypair::new());
+
+    let program_id = Pubkey::new_unique();
+    let (account, other_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut context = start(
+        program_id,
+        &[
+            (account, account_data(&stack)),
+            (other_account, account_data(&stack)),
+        ],
+    )
+    .await;
+
+    let execute = execute_instruction(program_id, account, Some(other_account), 0);
+    let result = process(&mut context, execute, &[]).await;
+    assert_error(result, VerifierError::UnsupportedAccountVersion);
+}
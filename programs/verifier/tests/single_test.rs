@@ -8,7 +8,7 @@ fn test_single_exponentiation() {
     stack.push_task(exp::Exp::new(2, 10));
 
     while !stack.is_empty_back() {
-        stack.execute();
+        stack.execute().unwrap();
     }
 
     let result = u128::from_be_bytes(stack.borrow_front().try_into().unwrap());
@@ -0,0 +1,31 @@
+use stark::keccak::KeccakHash;
+use stark::stark_proof::verify::Verify;
+use utils::Executable;
+use verifier::scheduler::{debug_task, task_frame};
+use verifier::state::BidirectionalStackAccount;
+
+#[test]
+fn invalid_task_bytes_are_not_read() {
+    let mut task = Verify::new().to_vec_with_type_tag();
+    assert!(debug_task(&task).is_some());
+    // No `VerifyStep` has this discriminant
+    task[4] = 0xff;
+    assert_eq!(debug_task(&task), None);
+
+    let mut task = KeccakHash::new(2).to_vec_with_type_tag();
+    assert!(task_frame(&task).is_some());
+    // The `finished` bool, after the length and the two enums
+    task[4 + size_of::<usize>() + 2] = 2;
+    assert_eq!(task_frame(&task), None);
+    assert_eq!(debug_task(&task), None);
+}
+
+#[test]
+#[should_panic(expected = "invalid task bytes")]
+fn invalid_task_bytes_are_not_executed() {
+    let mut stack = BidirectionalStackAccount::default();
+    let mut task = Verify::new().to_vec_with_type_tag();
+    task[4] = 0xff;
+    stack.schedule(&task).unwrap();
+    stack.execute().unwrap();
+}
@@ -1,7 +1,9 @@
+use stark::felt::Felt;
+use stark::stark_proof::reject::RejectReason;
 use stark::stark_proof::validate_public_input::ValidatePublicInput;
 use swiftness_proof_parser::{json_parser, transform::TransformTo, StarkProof as StarkProofParser};
-use utils::{BidirectionalStack, Scheduler};
-use verifier::state::BidirectionalStackAccount;
+use utils::{BidirectionalStack, Executable, Scheduler};
+use verifier::state::{BidirectionalStackAccount, VerificationState};
 
 #[test]
 fn validate_public_input() {
@@ -17,6 +19,32 @@ fn validate_public_input() {
 
     stack.push_task(ValidatePublicInput::new());
     while !stack.is_empty_back() {
-        stack.execute();
+        stack.execute().unwrap();
     }
+    // Validating the public input alone verifies no proof
+    assert_eq!(stack.status.state().unwrap(), VerificationState::Completed);
+}
+
+#[test]
+fn validate_public_input_rejects_invalid_layout() {
+    let mut stack = BidirectionalStackAccount::default();
+
+    let input = include_str!("../../../example_proof/saya.json");
+    let proof_json = serde_json::from_str::<json_parser::StarkProof>(input).unwrap();
+    let proof = StarkProofParser::try_from(proof_json).unwrap();
+
+    stack.proof = proof.transform_to();
+    stack.proof.public_input.layout = Felt::ZERO;
+
+    stack.push_task(ValidatePublicInput::new());
+    while !stack.is_empty_back() {
+        stack.execute().unwrap();
+    }
+
+    assert_eq!(stack.status.state().unwrap(), VerificationState::Rejected);
+    assert_eq!(
+        stack.status.error_code,
+        RejectReason::InvalidLayoutCode as u32
+    );
+    assert_eq!(stack.status.failed_task, ValidatePublicInput::TYPE_TAG);
 }
@@ -0,0 +1,83 @@
+use arithmetic::add::Add;
+use stark::felt::Felt;
+use stark::stark_proof::verify::{Verify, VerifyStep};
+use stark::stark_proof::VerifyPublicInput;
+use swiftness_proof_parser::{json_parser, transform::TransformTo, StarkProof as StarkProofParser};
+use utils::{BidirectionalStack, Executable, Scheduler};
+use verifier::state::{BidirectionalStackAccount, VerificationState};
+
+fn saya_stack() -> BidirectionalStackAccount {
+    let mut stack = BidirectionalStackAccount::default();
+
+    let input = include_str!("../../../example_proof/saya.json");
+    let proof_json = serde_json::from_str::<json_parser::StarkProof>(input).unwrap();
+    let proof = StarkProofParser::try_from(proof_json).unwrap();
+
+    stack.proof = proof.transform_to();
+    stack
+}
+
+fn run(stack: &mut BidirectionalStackAccount) -> VerificationState {
+    while !stack.is_empty_back() {
+        stack.execute().unwrap();
+    }
+    stack.status.state().unwrap()
+}
+
+#[test]
+fn public_input_root_is_completed() {
+    // `VerifyPublicInput` does not check the proof, so it is no proof fact
+    let mut stack = saya_stack();
+    stack.push_task(VerifyPublicInput::new());
+
+    assert_eq!(run(&mut stack), VerificationState::Completed);
+    assert_eq!(stack.status.root_task, VerifyPublicInput::TYPE_TAG);
+
+    // The hashes left on the front stack are recorded in the status
+    let program_hash = stack.borrow_front().to_vec();
+    stack.pop_front();
+    assert_eq!(stack.status.program_hash[..], program_hash);
+    assert_eq!(stack.status.output_hash[..], *stack.borrow_front());
+    assert_eq!(
+        Felt::from_bytes_be(&stack.status.program_hash),
+        Felt::from_hex("0x5ab580b04e3532b6b18f81cfa654a05e29dd8e2352d88df1e765a84072db07").unwrap()
+    );
+}
+
+#[test]
+fn other_root_is_completed() {
+    // Two hashes on the front stack do not make a verified fact
+    let mut stack = BidirectionalStackAccount::default();
+    stack.push_data(&Felt::ONE.to_bytes_be());
+    stack.push_data(&Felt::TWO.to_bytes_be());
+    stack.push_task(Add::new(1, 2));
+
+    assert_eq!(run(&mut stack), VerificationState::Completed);
+    assert_eq!(stack.status.root_task, 0);
+    assert_eq!(stack.status.program_hash, [0; 32]);
+}
+
+#[test]
+fn verification_root_above_another_task_is_completed() {
+    let mut stack = saya_stack();
+    stack.push_task(Add::new(1, 2));
+    stack.push_task(Verify::new());
+
+    assert_eq!(run(&mut stack), VerificationState::Completed);
+    assert_eq!(stack.status.root_task, 0);
+}
+
+#[test]
+fn verification_root_that_already_ran_is_completed() {
+    let mut stack = saya_stack();
+    stack.push_data(&Felt::ONE.to_bytes_be());
+    stack.push_data(&Felt::TWO.to_bytes_be());
+
+    // A `Verify` pushed in its `Done` step finishes right away
+    let mut task = Verify::new().to_vec_with_type_tag();
+    task[4] = VerifyStep::Done as u8;
+    stack.schedule(&task).unwrap();
+
+    assert_eq!(run(&mut stack), VerificationState::Completed);
+    assert_eq!(stack.status.root_task, 0);
+}
@@ -1,7 +1,12 @@
+use stark::felt::Felt;
+use stark::stark_proof::reject::RejectReason;
+use stark::stark_proof::validate_public_input::ValidatePublicInput;
 use stark::stark_proof::verify::Verify;
+use stark::swiftness::stark::types::StarkProof;
 use swiftness_proof_parser::{json_parser, transform::TransformTo, StarkProof as StarkProofParser};
-use utils::{BidirectionalStack, Scheduler};
-use verifier::state::BidirectionalStackAccount;
+use utils::{BidirectionalStack, Executable, Scheduler};
+use verifier::state::{BidirectionalStackAccount, VerificationState};
+use verifier::{verify_proof, VerifyError};
 
 #[test]
 fn verify() {
@@ -17,6 +22,39 @@ fn verify() {
 
     stack.push_task(Verify::new());
     while !stack.is_empty_back() {
-        stack.execute();
+        stack.execute().unwrap();
     }
+    assert_eq!(stack.status.state().unwrap(), VerificationState::Verified);
+    assert_eq!(stack.status.root_task, Verify::TYPE_TAG);
+
+    // The native entry point runs the same task graph to the same result
+    let program_hash = Felt::from_bytes_be_slice(stack.borrow_front());
+    stack.pop_front();
+    let output_hash = Felt::from_bytes_be_slice(stack.borrow_front());
+    assert_eq!(
+        Felt::from_bytes_be(&stack.status.program_hash),
+        program_hash
+    );
+    assert_eq!(Felt::from_bytes_be(&stack.status.output_hash), output_hash);
+    assert_eq!(verify_proof(&stack.proof), Ok((program_hash, output_hash)));
+}
+
+#[test]
+fn verify_proof_rejects_invalid_layout() {
+    let input = include_str!("../../../example_proof/saya.json");
+    let proof_json = serde_json::from_str::<json_parser::StarkProof>(input).unwrap();
+    let proof = StarkProofParser::try_from(proof_json).unwrap();
+
+    let mut proof_verifier: StarkProof = proof.transform_to();
+    proof_verifier.public_input.layout = Felt::ZERO;
+
+    let error = verify_proof(&proof_verifier).unwrap_err();
+    assert_eq!(
+        error,
+        VerifyError::Rejected {
+            code: RejectReason::InvalidLayoutCode as u32,
+            failed_task: ValidatePublicInput::TYPE_TAG,
+        }
+    );
+    assert_eq!(error.reason(), Some(RejectReason::InvalidLayoutCode));
 }
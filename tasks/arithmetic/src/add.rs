@@ -2,6 +2,7 @@ use utils::{impl_type_identifiable, BidirectionalStack};
 use utils::{Executable, TypeIdentifiable};
 
 #[repr(C)]
+#[derive(Debug)]
 pub struct Add {
     x: u128,
     y: u128,
@@ -3,6 +3,7 @@ use utils::{impl_type_identifiable, BidirectionalStack};
 use utils::{Executable, TypeIdentifiable};
 
 #[repr(C)]
+#[derive(Debug)]
 pub struct Exp {
     base: u128,
     exponent: u32,
@@ -17,6 +18,7 @@ impl Exp {
 }
 
 #[repr(C)]
+#[derive(Debug)]
 pub struct ExpInternal {
     base: u128,
     exponent: u32,
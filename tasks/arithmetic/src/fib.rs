@@ -2,6 +2,7 @@ use utils::{impl_type_identifiable, BidirectionalStack};
 use utils::{Executable, TypeIdentifiable};
 
 #[repr(C)]
+#[derive(Debug)]
 pub struct Fibonacci {
     n: u32,
 }
@@ -15,6 +16,7 @@ impl Fibonacci {
 }
 
 #[repr(C)]
+#[derive(Debug)]
 pub struct FibonacciCombiner {
     n: u32,
 }
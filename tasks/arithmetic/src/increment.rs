@@ -2,6 +2,7 @@ use utils::{impl_type_identifiable, BidirectionalStack};
 use utils::{Executable, TypeIdentifiable};
 
 #[repr(C)]
+#[derive(Debug)]
 pub struct Increment {}
 
 impl_type_identifiable!(Increment);
@@ -3,6 +3,7 @@ use utils::{impl_type_identifiable, BidirectionalStack};
 use utils::{Executable, TypeIdentifiable};
 
 #[repr(C)]
+#[derive(Debug)]
 pub struct Mul {
     x: u128,
     y: u128,
@@ -17,6 +18,7 @@ impl Mul {
 }
 
 #[repr(C)]
+#[derive(Debug)]
 pub struct MulInternal {
     x: u128,
     y: u128,
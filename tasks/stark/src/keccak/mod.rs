@@ -0,0 +1,146 @@
+use std::mem::offset_of;
+
+use utils::{
+    impl_type_identifiable, is_valid_discriminant, BidirectionalStack, Executable, Frame,
+    TypeIdentifiable,
+};
+
+use crate::{felt::Felt, stack::FeltStack};
+
+/// Bytes of a Keccak digest kept by `masked_160`
+const MASKED_BYTES: usize = 20;
+
+/// How a felt is turned into the 32 bytes that are hashed
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeltEncoding {
+    /// Big-endian bytes of the value, as Stone hashes Merkle nodes and
+    /// channel data
+    BigEndian,
+    /// Big-endian bytes of the Montgomery form `x * 2^256 mod p`, as Stone
+    /// serializes field elements in the leaves of a table commitment
+    Montgomery,
+}
+
+/// What a finished `KeccakHash` leaves on the front stack
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeccakOutput {
+    /// The low 160 bits of the digest as a felt, see `masked_160`
+    Masked160,
+    /// The 32 bytes of the digest, which may not fit in a felt
+    Digest,
+}
+
+impl FeltEncoding {
+    pub fn encode(self, felt: &Felt) -> [u8; 32] {
+        match self {
+            FeltEncoding::BigEndian => felt.to_bytes_be(),
+            FeltEncoding::Montgomery => {
+                // The internal representation is already in Montgomery form,
+                // with the most significant limb first
+                let mut bytes = [0u8; 32];
+                for (chunk, limb) in bytes.chunks_exact_mut(8).zip(felt.to_raw()) {
+                    chunk.copy_from_slice(&limb.to_be_bytes());
+                }
+                bytes
+            }
+        }
+    }
+}
+
+/// Keccak-256 digest of the felts in `encoding`, concatenated in order
+pub fn keccak_felts(felts: &[Felt], encoding: FeltEncoding) -> [u8; 32] {
+    let encoded: Vec<[u8; 32]> = felts.iter().map(|felt| encoding.encode(felt)).collect();
+    let slices: Vec<&[u8]> = encoded.iter().map(|bytes| bytes.as_slice()).collect();
+    solana_keccak_hasher::hashv(&slices).to_bytes()
+}
+
+/// Low 160 bits of a digest read as a big-endian number, how Stone turns a
+/// Keccak digest into a commitment node
+pub fn masked_160(digest: &[u8; 32]) -> Felt {
+    let mut bytes = [0u8; 32];
+    bytes[32 - MASKED_BYTES..].copy_from_slice(&digest[32 - MASKED_BYTES..]);
+    Felt::from_bytes_be(&bytes)
+}
+
+/// Keccak-256 hash of `len` felts, run in a single step with the Keccak
+/// syscall
+#[repr(C)]
+#[derive(Debug)]
+pub struct KeccakHash {
+    len: usize,
+    encoding: FeltEncoding,
+    output: KeccakOutput,
+    finished: bool,
+}
+
+impl_type_identifiable!(KeccakHash, 0xb231_3b06);
+
+impl KeccakHash {
+    /// Hash of the big-endian felts, masked to 160 bits
+    pub fn new(len: usize) -> Self {
+        Self::with_encoding(len, FeltEncoding::BigEndian, KeccakOutput::Masked160)
+    }
+
+    pub fn with_encoding(len: usize, encoding: FeltEncoding, output: KeccakOutput) -> Self {
+        Self {
+            len,
+            encoding,
+            output,
+            finished: false,
+        }
+    }
+
+    /// Push the inputs of the hash, the first one ends up on top
+    pub fn push_input<T: BidirectionalStack>(inputs: &[Felt], stack: &mut T) {
+        stack.push_felts(inputs);
+    }
+
+    /// Pop the masked hash a finished `KeccakHash` leaves in place of its
+    /// inputs
+    pub fn pop_result<T: BidirectionalStack>(stack: &mut T) -> Felt {
+        stack.pop_felt()
+    }
+
+    /// Pop the digest a finished `KeccakHash` with `KeccakOutput::Digest`
+    /// leaves in place of its inputs
+    pub fn pop_digest<T: BidirectionalStack>(stack: &mut T) -> [u8; 32] {
+        let digest = stack.borrow_front().try_into().unwrap();
+        stack.pop_front();
+        digest
+    }
+}
+
+impl Executable for KeccakHash {
+    fn execute<T: BidirectionalStack>(&mut self, stack: &mut T) -> Vec<Vec<u8>> {
+        let inputs: Vec<Felt> = (0..self.len).map(|_| stack.pop_felt()).collect();
+        let digest = keccak_felts(&inputs, self.encoding);
+        match self.output {
+            KeccakOutput::Masked160 => stack.push_felt(masked_160(&digest)),
+            KeccakOutput::Digest => stack.push_front(&digest).unwrap(),
+        }
+        self.finished = true;
+        vec![]
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.finished
+    }
+
+    fn frame(&self) -> Option<Frame> {
+        Some(Frame {
+            inputs: self.len,
+            outputs: 1,
+        })
+    }
+
+    fn is_valid_bytes(bytes: &[u8]) -> bool {
+        is_valid_discriminant(
+            bytes,
+            offset_of!(Self, encoding),
+            FeltEncoding::Montgomery as u8,
+        ) && is_valid_discriminant(bytes, offset_of!(Self, output), KeccakOutput::Digest as u8)
+            && is_valid_discriminant(bytes, offset_of!(Self, finished), true as u8)
+    }
+}
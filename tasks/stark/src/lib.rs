@@ -1,6 +1,20 @@
 pub mod felt;
 pub mod funvec;
+pub mod keccak;
 pub mod pedersen;
 pub mod poseidon;
+pub mod stack;
 pub mod stark_proof;
 pub mod swiftness;
+
+/// Compute units of an `Execute` step that hashing tasks built with a budget
+/// spend on hashing, leaving the rest of the 500k units the client requests
+/// to the scheduler and the account writes
+///
+/// A task divides its budget by the cost of one unit of its work, such as
+/// `HadesPermutation::COMPUTE_UNITS` or `pedersen::LOOKUP_COMPUTE_UNITS`, when
+/// it is created, so a step does the same work on-chain and in a local
+/// replay. These costs are estimates, not yet measured on-chain, kept on the
+/// high side so that a step stays within its budget; measure them with a
+/// `profile` report (see the README) and again when the hashing code changes.
+pub const HASH_STEP_COMPUTE_UNITS: u64 = 400_000;
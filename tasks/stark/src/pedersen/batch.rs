@@ -0,0 +1,67 @@
+use crate::felt::Felt;
+
+/// Sum of points given by their affine coordinates, `None` for the point at
+/// infinity
+///
+/// The points are added pairwise, level by level. The additions of a level
+/// are independent, so the denominators of their slopes are inverted together
+/// with a single field inversion (Montgomery's trick), which makes an affine
+/// addition cheaper than a projective one.
+pub fn sum_affine(mut points: Vec<(Felt, Felt)>) -> Option<(Felt, Felt)> {
+    while points.len() > 1 {
+        // Numerator and denominator of the slope of every pair, `None` when
+        // the two points cancel out
+        let fractions: Vec<Option<(Felt, Felt)>> = points
+            .chunks_exact(2)
+            .map(|pair| {
+                let [(x1, y1), (x2, y2)] = [pair[0], pair[1]];
+                if x1 != x2 {
+                    Some((y2 - y1, x2 - x1))
+                } else if y1 == y2 && y1 != Felt::ZERO {
+                    // Doubling, the curve has `alpha = 1`
+                    Some((Felt::THREE * x1.square() + Felt::ONE, y1.double()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        // Products of the denominators up to every pair
+        let mut products = Vec::with_capacity(fractions.len());
+        let mut product = Felt::ONE;
+        for (_, denominator) in fractions.iter().flatten() {
+            product *= *denominator;
+            products.push(product);
+        }
+
+        // Walk back from the inverse of the product to every single inverse
+        let mut inverse = product.inverse().expect("slope denominators are not zero");
+        let mut slopes = vec![None; fractions.len()];
+        for (slope, fraction) in slopes.iter_mut().zip(&fractions).rev() {
+            if let Some((numerator, denominator)) = fraction {
+                products.pop();
+                let denominator_inverse = match products.last() {
+                    Some(previous) => inverse * *previous,
+                    None => inverse,
+                };
+                inverse *= *denominator;
+                *slope = Some(*numerator * denominator_inverse);
+            }
+        }
+
+        let mut sums = Vec::with_capacity(points.len().div_ceil(2));
+        for (pair, slope) in points.chunks_exact(2).zip(slopes) {
+            if let Some(slope) = slope {
+                let [(x1, y1), (x2, _)] = [pair[0], pair[1]];
+                let x3 = slope.square() - x1 - x2;
+                sums.push((x3, slope * (x1 - x3) - y1));
+            }
+        }
+        // An odd point out goes on to the next level as is
+        if points.len() % 2 == 1 {
+            sums.extend(points.last().copied());
+        }
+        points = sums;
+    }
+    points.pop()
+}
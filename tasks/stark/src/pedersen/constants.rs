@@ -15,6 +15,9 @@ pub const SHIFT_POINT: ShortWeierstrassProjectivePoint<StarkCurve> =
         "0x03ca0cfe4b3bc6ddf346d49d06ea0ed34e621062c0e056c1d0405d266e10268a",
     );
 
+// The tables are only compiled into host builds, the program reads them from a
+// constants account instead, see `PedersenTables::of`
+#[cfg(not(target_os = "solana"))]
 pub static POINTS_P1: [ShortWeierstrassProjectivePoint<StarkCurve>; 930] = [
     StarkCurve::from_affine_hex_string(
         "0x0234287dcbaffe7f969c748655fca9e58fa8120b6d56eb0c1080d17957ebe47b",
@@ -3738,6 +3741,7 @@ pub static POINTS_P1: [ShortWeierstrassProjectivePoint<StarkCurve>; 930] = [
     ),
 ];
 
+#[cfg(not(target_os = "solana"))]
 pub static POINTS_P2: [ShortWeierstrassProjectivePoint<StarkCurve>; 15] = [
     StarkCurve::from_affine_hex_string(
         "0x04fa56f376c83db33f9dab2656558f3399099ec1de5e3018b7a6932dba8aa378",
@@ -3801,6 +3805,7 @@ pub static POINTS_P2: [ShortWeierstrassProjectivePoint<StarkCurve>; 15] = [
     ),
 ];
 
+#[cfg(not(target_os = "solana"))]
 pub static POINTS_P3: [ShortWeierstrassProjectivePoint<StarkCurve>; 930] = [
     StarkCurve::from_affine_hex_string(
         "0x04ba4cc166be8dec764910f75b45f74b40c690c74709e90f3aa372f0bd2d6997",
@@ -7524,6 +7529,7 @@ pub static POINTS_P3: [ShortWeierstrassProjectivePoint<StarkCurve>; 930] = [
     ),
 ];
 
+#[cfg(not(target_os = "solana"))]
 pub static POINTS_P4: [ShortWeierstrassProjectivePoint<StarkCurve>; 15] = [
     StarkCurve::from_affine_hex_string(
         "0x054302dcb0e6cc1c6e44cca8f61a63bb2ca65048d53fb325d36ff12c49a58202",
@@ -1,32 +1,69 @@
+#![allow(unexpected_cfgs)]
+
+use std::{mem::offset_of, ops::Range};
+
 use crate::felt::Felt;
-use crate::pedersen::constants::{POINTS_P1, POINTS_P2, POINTS_P3, POINTS_P4, SHIFT_POINT};
-use lambdaworks_math::elliptic_curve::short_weierstrass::{
-    curves::stark_curve::StarkCurve, point::ShortWeierstrassProjectivePoint,
+use crate::pedersen::constants::SHIFT_POINT;
+#[cfg(not(target_os = "solana"))]
+use crate::pedersen::constants::{POINTS_P1, POINTS_P2, POINTS_P3, POINTS_P4};
+use crate::stack::FeltStack;
+use lambdaworks_math::{
+    cyclic_group::IsGroup,
+    elliptic_curve::short_weierstrass::{
+        curves::stark_curve::StarkCurve, point::ShortWeierstrassProjectivePoint,
+    },
+};
+use utils::{
+    constants::find_table, impl_type_identifiable, is_valid_discriminant, BidirectionalStack,
+    Executable, Frame, TypeIdentifiable,
 };
-use utils::{impl_type_identifiable, BidirectionalStack, Executable, TypeIdentifiable};
 
+pub mod batch;
 pub mod constants;
 
+/// Compute units of one table lookup and its share of the batched additions,
+/// see `crate::HASH_STEP_COMPUTE_UNITS`
+pub const LOOKUP_COMPUTE_UNITS: u64 = 8_000;
+
+/// Bits of an input covered by the first and third tables, the remaining
+/// high bits are covered by the second and fourth
+pub const LOW_BITS: usize = 248;
+/// Bits of an input above `LOW_BITS`
+pub const HIGH_BITS: usize = 4;
+
+/// Id of the Pedersen tables in a constants region
+pub const PEDERSEN_TABLES_ID: u32 = u32::from_be_bytes(*b"PDSN");
+
 #[repr(C)]
 pub struct PedersenHash {
     phase: PerdersenPhase,
     acc: ShortWeierstrassProjectivePoint<StarkCurve>,
-    x: [bool; 256],
-    y: [bool; 256],
+    inputs: [Felt; 2],
+    next_lookup: usize,
+    lookups_per_step: usize,
 }
 
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PerdersenPhase {
-    LookupP1,
-    LookupP2,
-    LookupP3,
-    LookupP4,
-    Results,
+    /// The inputs are still on the data stack
+    Inputs,
+    Accumulate,
     Finished,
 }
 
-impl_type_identifiable!(PedersenHash);
+impl_type_identifiable!(PedersenHash, 0x40f2_9d72);
+
+// The accumulated point and the inputs are left out, they only add noise
+impl std::fmt::Debug for PedersenHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PedersenHash")
+            .field("phase", &self.phase)
+            .field("next_lookup", &self.next_lookup)
+            .field("lookups_per_step", &self.lookups_per_step)
+            .finish_non_exhaustive()
+    }
+}
 
 impl Default for PedersenHash {
     fn default() -> Self {
@@ -35,18 +72,32 @@ impl Default for PedersenHash {
 }
 
 impl PedersenHash {
+    /// Hash doing as many lookups per step as fit in `HASH_STEP_COMPUTE_UNITS`
     pub fn new() -> Self {
+        Self::with_budget(crate::HASH_STEP_COMPUTE_UNITS)
+    }
+
+    /// Hash doing as many lookups per step as fit in `compute_units`, at
+    /// least one
+    pub fn with_budget(compute_units: u64) -> Self {
         Self {
-            phase: PerdersenPhase::LookupP1,
+            phase: PerdersenPhase::Inputs,
             acc: SHIFT_POINT,
-            x: [false; 256],
-            y: [false; 256],
+            inputs: [Felt::ZERO; 2],
+            next_lookup: 0,
+            lookups_per_step: (compute_units / LOOKUP_COMPUTE_UNITS).max(1) as usize,
         }
     }
 
+    /// Push the inputs of the hash, `y` ends up on top
     pub fn push_input<T: BidirectionalStack>(x: Felt, y: Felt, stack: &mut T) {
-        stack.push_front(&x.to_bytes_be()).unwrap();
-        stack.push_front(&y.to_bytes_be()).unwrap();
+        stack.push_felts(&[y, x]);
+    }
+
+    /// Pop the hash, the only value a finished `PedersenHash` leaves in place
+    /// of its inputs
+    pub fn pop_result<T: BidirectionalStack>(stack: &mut T) -> Felt {
+        stack.pop_felt()
     }
 }
 
@@ -62,168 +113,283 @@ fn bools_to_usize_le(bools: &[bool]) -> usize {
 }
 
 impl Executable for PedersenHash {
+    const READS_CONSTANTS: bool = true;
+
     fn execute<T: BidirectionalStack>(&mut self, stack: &mut T) -> Vec<Vec<u8>> {
-        match self.phase {
-            PerdersenPhase::LookupP1 => {
-                let y = Felt::from_bytes_be(stack.borrow_front().try_into().unwrap());
-                stack.pop_front();
-                let x = Felt::from_bytes_be(stack.borrow_front().try_into().unwrap());
-                stack.pop_front();
-                let x = x.to_bits_le();
-                let y = y.to_bits_le();
-                self.x = x;
-                self.y = y;
-
-                stack.push_front(&self.acc.x().to_bytes_be()).unwrap();
-                stack.push_front(&self.acc.y().to_bytes_be()).unwrap();
-                stack.push_front(&self.acc.z().to_bytes_be()).unwrap();
-
-                self.phase = PerdersenPhase::LookupP2;
-                vec![LookupAndAccumulate::new(&self.x[..248], 1).to_vec_with_type_tag()]
-            }
-            PerdersenPhase::LookupP2 => {
-                self.phase = PerdersenPhase::LookupP3;
-                vec![LookupAndAccumulate::new(&self.x[248..252], 2).to_vec_with_type_tag()]
-            }
-            PerdersenPhase::LookupP3 => {
-                self.phase = PerdersenPhase::LookupP4;
-                vec![LookupAndAccumulate::new(&self.y[..248], 3).to_vec_with_type_tag()]
-            }
-            PerdersenPhase::LookupP4 => {
-                self.phase = PerdersenPhase::Results;
-                vec![LookupAndAccumulate::new(&self.y[248..252], 4).to_vec_with_type_tag()]
-            }
-            PerdersenPhase::Results => {
-                let z = Felt::from_bytes_be(stack.borrow_front().try_into().unwrap());
-                stack.pop_front();
-                let y = Felt::from_bytes_be(stack.borrow_front().try_into().unwrap());
-                stack.pop_front();
-                let x = Felt::from_bytes_be(stack.borrow_front().try_into().unwrap());
-                stack.pop_front();
+        if self.phase == PerdersenPhase::Inputs {
+            let [y, x] = stack.pop_felts();
+            self.inputs = [x, y];
+            self.phase = PerdersenPhase::Accumulate;
+        }
 
-                self.acc =
-                    ShortWeierstrassProjectivePoint::<StarkCurve>::new([x.0, y.0, z.0]).unwrap();
+        if self.phase == PerdersenPhase::Accumulate {
+            let tables = PedersenTables::of(stack);
+            let total = tables.lookups();
+            let end = total.min(self.next_lookup + self.lookups_per_step);
 
-                let result = *self.acc.to_affine().x();
-                stack.push_front(&result.to_bytes_be()).unwrap();
+            // The selected points are summed in affine coordinates, then added
+            // to the accumulator with a single mixed addition
+            let points = tables.select(&self.inputs, self.next_lookup..end);
+            if let Some((x, y)) = batch::sum_affine(points) {
+                let point = ShortWeierstrassProjectivePoint::new([x.0, y.0, Felt::ONE.0]).unwrap();
+                self.acc = self.acc.operate_with_affine(&point);
+            }
+            self.next_lookup = end;
 
+            if self.next_lookup == total {
+                stack.push_felt(Felt(*self.acc.to_affine().x()));
                 self.phase = PerdersenPhase::Finished;
-                vec![]
-            }
-            PerdersenPhase::Finished => {
-                vec![]
             }
         }
+
+        vec![]
     }
 
     fn is_finished(&mut self) -> bool {
         self.phase == PerdersenPhase::Finished
     }
+
+    fn frame(&self) -> Option<Frame> {
+        Some(Frame {
+            inputs: 2,
+            outputs: 1,
+        })
+    }
+
+    fn is_valid_bytes(bytes: &[u8]) -> bool {
+        is_valid_discriminant(
+            bytes,
+            offset_of!(Self, phase),
+            PerdersenPhase::Finished as u8,
+        )
+    }
 }
 
-#[repr(C)]
-pub struct LookupAndAccumulate {
-    phase: LookupAndAccumulatePhase,
-    acc: ShortWeierstrassProjectivePoint<StarkCurve>,
-    bits: [bool; 248],
-    bits_len: usize,
-    table_index: u8,
-    chunk_index: usize,
+/// Bytes of an encoded table point: its affine `x` then `y`, each as the four
+/// limbs of its Montgomery form, least significant first, as little-endian
+/// `u64`s
+pub const POINT_BYTES: usize = 64;
+
+fn encode_point(point: &ShortWeierstrassProjectivePoint<StarkCurve>, bytes: &mut Vec<u8>) {
+    let point = point.to_affine();
+    for coordinate in [Felt(*point.x()), Felt(*point.y())] {
+        for limb in coordinate.to_raw_reversed() {
+            bytes.extend_from_slice(&limb.to_le_bytes());
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum LookupAndAccumulatePhase {
-    Lookup,
-    Accumulate,
-    Finished,
+fn decode_coordinate(bytes: &[u8]) -> Felt {
+    let mut limbs = [0; 4];
+    for (limb, chunk) in limbs.iter_mut().rev().zip(bytes.chunks_exact(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    Felt::from_raw(limbs)
 }
 
-impl_type_identifiable!(LookupAndAccumulate);
+/// Multiples of one Pedersen generator for the windows of part of a scalar
+///
+/// The point at `window * table_size() + digit - 1` is
+/// `digit * 2^(window * window_bits) * generator`, encoded in `POINT_BYTES`.
+#[derive(Debug, Clone, Copy)]
+pub struct WindowTable<'a> {
+    pub window_bits: usize,
+    /// Bits of the scalar covered by the table
+    pub scalar_bits: usize,
+    pub points: &'a [u8],
+}
 
-impl LookupAndAccumulate {
-    pub fn new(bits: &[bool], table_index: u8) -> Self {
-        let mut bits_array = [false; 248];
-        let len = bits.len();
-        bits_array[..len].copy_from_slice(&bits[..len]);
+impl WindowTable<'_> {
+    /// Points of every window, all digits but zero
+    pub fn table_size(&self) -> usize {
+        (1 << self.window_bits) - 1
+    }
 
-        Self {
-            phase: LookupAndAccumulatePhase::Lookup,
-            acc: SHIFT_POINT,
-            bits: bits_array,
-            bits_len: len,
-            table_index,
-            chunk_index: 0,
+    pub fn windows(&self) -> usize {
+        self.scalar_bits.div_ceil(self.window_bits)
+    }
+
+    /// Affine coordinates of the point of `digit` in `window`, `None` for the
+    /// zero digit
+    pub fn lookup(&self, window: usize, digit: usize) -> Option<(Felt, Felt)> {
+        let digit = digit.checked_sub(1)?;
+        let start = (window * self.table_size() + digit) * POINT_BYTES;
+        let point = &self.points[start..start + POINT_BYTES];
+        Some((
+            decode_coordinate(&point[..POINT_BYTES / 2]),
+            decode_coordinate(&point[POINT_BYTES / 2..]),
+        ))
+    }
+
+    /// Compute the points of a table, in the order `lookup` reads them
+    pub fn generate(
+        generator: &ShortWeierstrassProjectivePoint<StarkCurve>,
+        window_bits: usize,
+        scalar_bits: usize,
+    ) -> Vec<ShortWeierstrassProjectivePoint<StarkCurve>> {
+        let table_size = (1 << window_bits) - 1;
+        let windows = scalar_bits.div_ceil(window_bits);
+        let mut points = Vec::with_capacity(windows * table_size);
+
+        let mut base = generator.clone();
+        for _ in 0..windows {
+            let mut point = base.clone();
+            for _ in 0..table_size {
+                points.push(point.to_affine());
+                point = point.operate_with(&base);
+            }
+            // `point` went one digit past the table, to the next window base
+            base = point;
         }
+        points
     }
 }
 
-impl Executable for LookupAndAccumulate {
-    fn execute<T: BidirectionalStack>(&mut self, stack: &mut T) -> Vec<Vec<u8>> {
-        match self.phase {
-            LookupAndAccumulatePhase::Lookup => {
-                let z = Felt::from_bytes_be(stack.borrow_front().try_into().unwrap());
-                stack.pop_front();
-                let y = Felt::from_bytes_be(stack.borrow_front().try_into().unwrap());
-                stack.pop_front();
-                let x = Felt::from_bytes_be(stack.borrow_front().try_into().unwrap());
-                stack.pop_front();
-
-                self.acc =
-                    ShortWeierstrassProjectivePoint::<StarkCurve>::new([x.0, y.0, z.0]).unwrap();
-                self.phase = LookupAndAccumulatePhase::Accumulate;
-                vec![]
-            }
-            LookupAndAccumulatePhase::Accumulate => {
-                const CHUNK_SIZE: usize = 10;
-
-                let prep: &[ShortWeierstrassProjectivePoint<StarkCurve>] = match self.table_index {
-                    1 => &POINTS_P1,
-                    2 => &POINTS_P2,
-                    3 => &POINTS_P3,
-                    4 => &POINTS_P4,
-                    _ => panic!("Invalid table index"),
-                };
-
-                let bits = &self.bits[..self.bits_len];
-                let start_chunk = self.chunk_index;
-
-                #[allow(clippy::double_ended_iterator_last)]
-                let processed = bits
-                    .chunks(PedersenHash::CURVE_CONST_BITS)
-                    .enumerate()
-                    .skip(start_chunk)
-                    .take(CHUNK_SIZE)
-                    .map(|(i, chunk)| {
-                        let offset = bools_to_usize_le(chunk);
-                        if offset > 0 {
-                            self.acc = self.acc.operate_with_affine(
-                                &prep[i * PedersenHash::TABLE_SIZE + offset - 1],
-                            );
-                        }
-                        i + 1
-                    })
-                    .last()
-                    .unwrap_or(start_chunk);
-
-                self.chunk_index = processed;
-
-                let total_chunks = bits.len().div_ceil(PedersenHash::CURVE_CONST_BITS);
-                if self.chunk_index >= total_chunks {
-                    stack.push_front(&self.acc.x().to_bytes_be()).unwrap();
-                    stack.push_front(&self.acc.y().to_bytes_be()).unwrap();
-                    stack.push_front(&self.acc.z().to_bytes_be()).unwrap();
-                    self.phase = LookupAndAccumulatePhase::Finished;
-                }
-
-                vec![]
-            }
-            LookupAndAccumulatePhase::Finished => {
-                vec![]
-            }
+/// The four tables of a Pedersen hash: the low and high bits of the first
+/// input, then of the second
+#[derive(Debug, Clone, Copy)]
+pub struct PedersenTables<'a>(pub [WindowTable<'a>; 4]);
+
+#[cfg(not(target_os = "solana"))]
+impl PedersenTables<'static> {
+    /// Tables compiled into host builds, with 4-bit windows, encoded on first
+    /// use
+    pub fn builtin() -> Self {
+        static BYTES: std::sync::OnceLock<Vec<u8>> = std::sync::OnceLock::new();
+        let bytes = BYTES.get_or_init(|| {
+            Self::encode(
+                PedersenHash::CURVE_CONST_BITS,
+                [
+                    &POINTS_P1[..],
+                    &POINTS_P2[..],
+                    &POINTS_P3[..],
+                    &POINTS_P4[..],
+                ],
+            )
+        });
+        Self::from_bytes(bytes).expect("malformed built-in Pedersen tables")
+    }
+
+    /// Tables with `window_bits`-bit windows in the layout `from_bytes` reads,
+    /// to be stored as `PEDERSEN_TABLES_ID` in a constants account. Windows
+    /// of the high tables are no wider than `HIGH_BITS`.
+    pub fn generate_bytes(window_bits: usize) -> Vec<u8> {
+        let generators = [&POINTS_P1[0], &POINTS_P2[0], &POINTS_P3[0], &POINTS_P4[0]];
+        let scalar_bits = [LOW_BITS, HIGH_BITS, LOW_BITS, HIGH_BITS];
+        let tables: Vec<_> = generators
+            .into_iter()
+            .zip(scalar_bits)
+            .map(|(generator, scalar_bits)| {
+                WindowTable::generate(generator, window_bits.min(scalar_bits), scalar_bits)
+            })
+            .collect();
+        Self::encode(
+            window_bits,
+            [
+                &tables[0][..],
+                &tables[1][..],
+                &tables[2][..],
+                &tables[3][..],
+            ],
+        )
+    }
+
+    fn encode(
+        window_bits: usize,
+        tables: [&[ShortWeierstrassProjectivePoint<StarkCurve>]; 4],
+    ) -> Vec<u8> {
+        let points: usize = tables.iter().map(|table| table.len()).sum();
+        let mut bytes = Vec::with_capacity(8 + points * POINT_BYTES);
+        bytes.extend_from_slice(&(window_bits as u64).to_le_bytes());
+        for point in tables.into_iter().flatten() {
+            encode_point(point, &mut bytes);
         }
+        bytes
     }
+}
 
-    fn is_finished(&mut self) -> bool {
-        self.phase == LookupAndAccumulatePhase::Finished
+impl<'a> PedersenTables<'a> {
+    /// Tables stored as `PEDERSEN_TABLES_ID` in the constants of the stack,
+    /// falling back to the built-in tables in host builds
+    ///
+    /// # Panics
+    ///
+    /// If the constants hold malformed tables, or hold none in a program
+    /// build, where the constants account must be passed to `Execute`.
+    pub fn of<T: BidirectionalStack>(stack: &'a T) -> Self {
+        match find_table(stack.get_constants_bytes(), PEDERSEN_TABLES_ID) {
+            Some(bytes) => Self::from_bytes(bytes).expect("malformed Pedersen tables"),
+            #[cfg(not(target_os = "solana"))]
+            None => PedersenTables::builtin(),
+            #[cfg(target_os = "solana")]
+            None => panic!("no Pedersen tables, pass the constants account"),
+        }
+    }
+
+    /// Tables from their window width (little-endian `u64`) followed by the
+    /// points of the four tables, `POINT_BYTES` each, `None` if the size of
+    /// the bytes does not match
+    ///
+    /// A table covering fewer bits than the window width has a single window
+    /// of its own width, so the high tables hold `(1 << HIGH_BITS) - 1`
+    /// points once windows are wider than `HIGH_BITS`.
+    pub fn from_bytes(bytes: &'a [u8]) -> Option<Self> {
+        let (window_bits, mut points) = bytes.split_first_chunk::<8>()?;
+        let window_bits = u64::from_le_bytes(*window_bits) as usize;
+        if !(1..=16).contains(&window_bits) {
+            return None;
+        }
+
+        let mut table = |scalar_bits: usize| {
+            let window_bits = window_bits.min(scalar_bits);
+            let len = scalar_bits.div_ceil(window_bits) * ((1 << window_bits) - 1) * POINT_BYTES;
+            let (table, rest) = points.split_at_checked(len)?;
+            points = rest;
+            Some(WindowTable {
+                window_bits,
+                scalar_bits,
+                points: table,
+            })
+        };
+        let tables = [
+            table(LOW_BITS)?,
+            table(HIGH_BITS)?,
+            table(LOW_BITS)?,
+            table(HIGH_BITS)?,
+        ];
+        points.is_empty().then_some(Self(tables))
+    }
+
+    /// Number of lookups of a hash, one per window of every table
+    pub fn lookups(&self) -> usize {
+        self.0.iter().map(WindowTable::windows).sum()
+    }
+
+    /// Affine coordinates of the points the given lookups of hashing `inputs`
+    /// select, zero digits select nothing
+    ///
+    /// Lookups are numbered through the windows of the tables in order.
+    pub fn select(&self, inputs: &[Felt; 2], lookups: Range<usize>) -> Vec<(Felt, Felt)> {
+        let bits = [inputs[0].to_bits_le(), inputs[1].to_bits_le()];
+        let mut points = Vec::with_capacity(lookups.len());
+
+        let mut first_lookup = 0;
+        for (part, table) in self.0.iter().enumerate() {
+            let windows = table.windows();
+            let start = lookups.start.max(first_lookup);
+            let end = lookups.end.min(first_lookup + windows);
+
+            let input_bits = &bits[part / 2];
+            let bit_offset = if part % 2 == 0 { 0 } else { LOW_BITS };
+            for window in start.saturating_sub(first_lookup)..end.saturating_sub(first_lookup) {
+                let window_start = bit_offset + window * table.window_bits;
+                let window_end =
+                    (window_start + table.window_bits).min(bit_offset + table.scalar_bits);
+                let digit = bools_to_usize_le(&input_bits[window_start..window_end]);
+                points.extend(table.lookup(window, digit));
+            }
+            first_lookup += windows;
+        }
+        points
     }
 }
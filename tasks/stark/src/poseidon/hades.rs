@@ -1,7 +1,11 @@
+use std::mem::offset_of;
+
 use crate::felt::Felt;
-use utils::{impl_type_identifiable, BidirectionalStack};
-use utils::{Executable, TypeIdentifiable};
+use crate::stack::FeltStack;
+use utils::{impl_type_identifiable, is_valid_discriminant, BidirectionalStack};
+use utils::{Executable, Frame, TypeIdentifiable};
 
+#[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HadesPhase {
     FirstHalfFullRounds,
@@ -10,15 +14,20 @@ pub enum HadesPhase {
 }
 
 #[repr(C)]
+#[derive(Debug)]
 pub struct HadesPermutation {
     state: [Felt; 3],
     phase: HadesPhase,
     constants_index: usize,
 }
 
-impl_type_identifiable!(HadesPermutation);
+impl_type_identifiable!(HadesPermutation, 0xaed7_b963);
 
 impl HadesPermutation {
+    /// Compute units of one absorb and full permutation on-chain, see
+    /// `crate::HASH_STEP_COMPUTE_UNITS`
+    pub const COMPUTE_UNITS: u64 = 120_000;
+
     pub fn new(state: [Felt; 3]) -> Self {
         Self {
             state,
@@ -27,6 +36,12 @@ impl HadesPermutation {
         }
     }
 
+    /// Pop the state a finished permutation leaves on the front stack,
+    /// `state[0]` is on top
+    pub fn pop_state<T: BidirectionalStack>(stack: &mut T) -> [Felt; 3] {
+        stack.pop_felts()
+    }
+
     /// Redefined mix function for optimization purposes
     #[inline(always)]
     fn mix(state: &mut [Felt]) {
@@ -57,42 +72,60 @@ impl HadesPermutation {
         // Mix step
         Self::mix(&mut self.state);
     }
+
+    /// First half of the full rounds and of the partial rounds
+    fn first_half(&mut self) {
+        for _ in 0..Self::N_FULL_ROUNDS / 2 {
+            self.full_round();
+
+            self.constants_index += Self::N_ROUND_CONSTANTS_COLS;
+        }
+        for _ in 0..Self::N_PARTIAL_ROUNDS / 2 {
+            self.partial_round();
+
+            self.constants_index += 1;
+        }
+    }
+
+    /// Remaining partial rounds and second half of the full rounds
+    fn second_half(&mut self) {
+        for _ in 0..(Self::N_PARTIAL_ROUNDS - Self::N_PARTIAL_ROUNDS / 2) {
+            self.partial_round();
+
+            self.constants_index += 1;
+        }
+        for _ in 0..Self::N_FULL_ROUNDS / 2 {
+            self.full_round();
+
+            self.constants_index += Self::N_ROUND_CONSTANTS_COLS;
+        }
+    }
+
+    /// Run the whole permutation in place of a task, with the same result
+    pub fn permute(state: [Felt; 3]) -> [Felt; 3] {
+        let mut permutation = Self::new(state);
+        permutation.first_half();
+        permutation.second_half();
+        permutation.state
+    }
+
+    /// Number of absorb and permute rounds that fit in `compute_units`, at
+    /// least one
+    pub fn rounds_within(compute_units: u64) -> usize {
+        (compute_units / Self::COMPUTE_UNITS).max(1) as usize
+    }
 }
 
 impl Executable for HadesPermutation {
     fn execute<T: BidirectionalStack>(&mut self, stack: &mut T) -> Vec<Vec<u8>> {
         match self.phase {
             HadesPhase::FirstHalfFullRounds => {
-                // First half of full rounds
-                for _ in 0..Self::N_FULL_ROUNDS / 2 {
-                    self.full_round();
-
-                    self.constants_index += Self::N_ROUND_CONSTANTS_COLS;
-                }
-                // Partial rounds
-                for _ in 0..Self::N_PARTIAL_ROUNDS / 2 {
-                    self.partial_round();
-
-                    self.constants_index += 1;
-                }
+                self.first_half();
                 self.phase = HadesPhase::SecondHalfFullRounds;
             }
             HadesPhase::SecondHalfFullRounds => {
-                // Partial rounds
-                for _ in 0..(Self::N_PARTIAL_ROUNDS - Self::N_PARTIAL_ROUNDS / 2) {
-                    self.partial_round();
-
-                    self.constants_index += 1;
-                }
-                // Second half of full rounds
-                for _ in 0..Self::N_FULL_ROUNDS / 2 {
-                    self.full_round();
-
-                    self.constants_index += Self::N_ROUND_CONSTANTS_COLS;
-                }
-                self.state.iter().rev().for_each(|value| {
-                    stack.push_front(&value.to_bytes_be()).unwrap();
-                });
+                self.second_half();
+                stack.push_felts(&self.state);
                 self.phase = HadesPhase::Finished;
             }
             HadesPhase::Finished => {}
@@ -104,4 +137,16 @@ impl Executable for HadesPermutation {
     fn is_finished(&mut self) -> bool {
         self.phase == HadesPhase::Finished
     }
+
+    /// The final state is left on the data stack
+    fn frame(&self) -> Option<Frame> {
+        Some(Frame {
+            inputs: 0,
+            outputs: 3,
+        })
+    }
+
+    fn is_valid_bytes(bytes: &[u8]) -> bool {
+        is_valid_discriminant(bytes, offset_of!(Self, phase), HadesPhase::Finished as u8)
+    }
 }
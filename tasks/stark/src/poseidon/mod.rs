@@ -1,61 +1,107 @@
 pub mod constants;
 pub mod hades;
 
-use utils::{impl_type_identifiable, BidirectionalStack, Executable, TypeIdentifiable};
+use std::cmp::Ordering;
 
-use crate::{felt::Felt, poseidon::hades::HadesPermutation};
+use utils::{impl_type_identifiable, BidirectionalStack, Executable, Frame, TypeIdentifiable};
+
+use crate::{
+    felt::Felt, poseidon::hades::HadesPermutation, stack::FeltStack,
+    swiftness::stark::types::StarkProof,
+};
 
 #[repr(C)]
+#[derive(Debug)]
 pub struct PoseidonHashMany {
     state: [Felt; 3],
     input_length: usize,
     counter: usize,
+    /// Absorb and permute rounds run inline per step, zero to run every
+    /// permutation as a `HadesPermutation` task
+    rounds_per_step: usize,
 }
 
-impl_type_identifiable!(PoseidonHashMany);
+// Hashes run over several steps, so their tags are pinned like the ones of
+// the proof tasks
+impl_type_identifiable!(PoseidonHashMany, 0xaf32_f341);
 
 impl PoseidonHashMany {
+    /// Hash absorbing two inputs per step, each permutation split over the two
+    /// steps of a `HadesPermutation` task
     pub fn new(inputs_len: usize) -> Self {
         Self {
             state: [Felt::ZERO; 3],
             input_length: (inputs_len + 1).div_ceil(2) * 2,
             counter: 0,
+            rounds_per_step: 0,
         }
     }
 
+    /// Hash running as many absorb and permute rounds per step as fit in
+    /// `compute_units` (see `crate::HASH_STEP_COMPUTE_UNITS`), with the same
+    /// result as `new`
+    pub fn with_budget(inputs_len: usize, compute_units: u64) -> Self {
+        Self {
+            rounds_per_step: HadesPermutation::rounds_within(compute_units),
+            ..Self::new(inputs_len)
+        }
+    }
+
+    /// Push the inputs of the hash with their padding and the initial state
+    ///
+    /// Inputs that are only available one by one are pushed the same way:
+    /// `push_padding`, the inputs from the last to the first, then
+    /// `push_initial_state`.
     pub fn push_input<T: BidirectionalStack>(inputs: &[Felt], stack: &mut T) {
-        let inputs_len = inputs.len() + 1;
+        Self::push_padding(inputs.len(), stack);
+        stack.push_felts(inputs);
+        Self::push_initial_state(stack);
+    }
+
+    /// Push the padding that goes below `inputs_len` inputs
+    pub fn push_padding<T: BidirectionalStack>(inputs_len: usize, stack: &mut T) {
+        let inputs_len = inputs_len + 1;
         let zero_count = inputs_len.div_ceil(2) * 2 - inputs_len;
         for _ in 0..zero_count {
-            stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
+            stack.push_felt(Felt::ZERO);
         }
-        stack.push_front(&Felt::ONE.to_bytes_be()).unwrap();
-
-        inputs.iter().rev().for_each(|value| {
-            stack.push_front(&value.to_bytes_be()).unwrap();
-        });
-        stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
-        stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
-        stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
+        stack.push_felt(Felt::ONE);
+    }
+
+    /// Push the zero state the permutations start from, on top of the inputs
+    pub fn push_initial_state<T: BidirectionalStack>(stack: &mut T) {
+        stack.push_felts(&[Felt::ZERO; 3]);
+    }
+
+    /// Pop the hash together with the rest of the final permutation state,
+    /// which a finished `PoseidonHashMany` leaves in place of its inputs
+    pub fn pop_result<T: BidirectionalStack>(stack: &mut T) -> Felt {
+        let [hash, _, _] = HadesPermutation::pop_state(stack);
+        hash
     }
 }
 
 impl Executable for PoseidonHashMany {
     fn execute<T: BidirectionalStack>(&mut self, stack: &mut T) -> Vec<Vec<u8>> {
-        let s1 = Felt::from_bytes_be(stack.borrow_front().try_into().unwrap());
-        stack.pop_front();
-
-        let s2 = Felt::from_bytes_be(stack.borrow_front().try_into().unwrap());
-        stack.pop_front();
-
-        let s3 = Felt::from_bytes_be(stack.borrow_front().try_into().unwrap());
-        stack.pop_front();
-
-        let v1 = Felt::from_bytes_be(stack.borrow_front().try_into().unwrap());
-        stack.pop_front();
+        if self.rounds_per_step > 0 {
+            // The state stays on top of the remaining inputs between steps
+            let mut state = HadesPermutation::pop_state(stack);
+            for _ in 0..self.rounds_per_step {
+                if self.counter >= self.input_length {
+                    break;
+                }
+                let [v1, v2] = stack.pop_felts();
+                state[0] += v1;
+                state[1] += v2;
+                state = HadesPermutation::permute(state);
+                self.counter += 2;
+            }
+            stack.push_felts(&state);
+            return vec![];
+        }
 
-        let v2 = Felt::from_bytes_be(stack.borrow_front().try_into().unwrap());
-        stack.pop_front();
+        let [s1, s2, s3] = HadesPermutation::pop_state(stack);
+        let [v1, v2] = stack.pop_felts();
 
         self.state[0] = s1 + v1;
         self.state[1] = s2 + v2;
@@ -69,4 +115,111 @@ impl Executable for PoseidonHashMany {
     fn is_finished(&mut self) -> bool {
         self.counter >= self.input_length
     }
+
+    /// The padded inputs below the initial state are replaced by the final state
+    fn frame(&self) -> Option<Frame> {
+        Some(Frame {
+            inputs: self.input_length + 3,
+            outputs: 3,
+        })
+    }
+}
+
+/// Poseidon hash of the values of `len` consecutive cells of the proof's
+/// `public_input.main_page`, starting at cell `start`
+///
+/// Same result as `PoseidonHashMany` over those values, but the inputs and
+/// the padding are read from the proof as they are absorbed instead of being
+/// pushed on the front stack first, so the range is only bounded by the proof
+/// itself. The range must lie inside `main_page`. The final permutation state
+/// is left on the front stack, read it with `PoseidonHashMany::pop_result`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct PoseidonHashMainPage {
+    state: [Felt; 3],
+    start: usize,
+    len: usize,
+    absorbed: usize,
+    /// Absorb and permute rounds run inline per step, see `PoseidonHashMany`
+    rounds_per_step: usize,
+}
+
+impl_type_identifiable!(PoseidonHashMainPage, 0x55f6_6d5c);
+
+impl PoseidonHashMainPage {
+    pub fn new(start: usize, len: usize) -> Self {
+        Self {
+            state: [Felt::ZERO; 3],
+            start,
+            len,
+            absorbed: 0,
+            rounds_per_step: 0,
+        }
+    }
+
+    /// Hash running as many rounds per step as fit in `compute_units`, like
+    /// `PoseidonHashMany::with_budget`
+    pub fn with_budget(start: usize, len: usize, compute_units: u64) -> Self {
+        Self {
+            rounds_per_step: HadesPermutation::rounds_within(compute_units),
+            ..Self::new(start, len)
+        }
+    }
+
+    /// Input `index` of the padded inputs: the cell values, a one, then a
+    /// zero if needed to even out the length
+    fn input<T: BidirectionalStack>(&self, index: usize, stack: &T) -> Felt {
+        match index.cmp(&self.len) {
+            Ordering::Less => {
+                let proof: &StarkProof = stack.get_proof_reference();
+                proof.public_input.main_page.0.as_slice()[self.start + index].value
+            }
+            Ordering::Equal => Felt::ONE,
+            Ordering::Greater => Felt::ZERO,
+        }
+    }
+}
+
+impl Executable for PoseidonHashMainPage {
+    fn execute<T: BidirectionalStack>(&mut self, stack: &mut T) -> Vec<Vec<u8>> {
+        if self.rounds_per_step > 0 {
+            // The state is kept in the task until the last round
+            for _ in 0..self.rounds_per_step {
+                if self.absorbed > self.len {
+                    break;
+                }
+                self.state[0] += self.input(self.absorbed, stack);
+                self.state[1] += self.input(self.absorbed + 1, stack);
+                self.state = HadesPermutation::permute(self.state);
+                self.absorbed += 2;
+            }
+            if self.absorbed > self.len {
+                stack.push_felts(&self.state);
+            }
+            return vec![];
+        }
+
+        // The first permutation starts from the zero state
+        if self.absorbed > 0 {
+            self.state = HadesPermutation::pop_state(stack);
+        }
+
+        self.state[0] += self.input(self.absorbed, stack);
+        self.state[1] += self.input(self.absorbed + 1, stack);
+        self.absorbed += 2;
+
+        vec![HadesPermutation::new(self.state).to_vec_with_type_tag()]
+    }
+
+    fn is_finished(&mut self) -> bool {
+        self.absorbed > self.len
+    }
+
+    /// Nothing is read from the data stack, the final state is left on it
+    fn frame(&self) -> Option<Frame> {
+        Some(Frame {
+            inputs: 0,
+            outputs: 3,
+        })
+    }
 }
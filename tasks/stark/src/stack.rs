@@ -0,0 +1,69 @@
+use lambdaworks_math::elliptic_curve::short_weierstrass::{
+    curves::stark_curve::StarkCurve, point::ShortWeierstrassProjectivePoint,
+};
+use utils::BidirectionalStack;
+
+use crate::felt::Felt;
+
+/// Typed access to the front (data) stack, where every value is stored as a
+/// separate 32 byte big-endian item
+pub trait FeltStack: BidirectionalStack {
+    fn push_felt(&mut self, felt: Felt) {
+        self.push_front(&felt.to_bytes_be()).unwrap();
+    }
+
+    /// Pop the felt on top of the front stack
+    ///
+    /// Panics if the top item is not 32 bytes long, which means the caller
+    /// and the task that produced the data disagree about its layout.
+    fn pop_felt(&mut self) -> Felt {
+        let bytes: &[u8; 32] = self
+            .borrow_front()
+            .try_into()
+            .expect("front stack item is not a felt");
+        let felt = Felt::from_bytes_be(bytes);
+        self.pop_front();
+        felt
+    }
+
+    /// Read the felt on top of the front stack without popping it
+    fn peek_felt(&self) -> Felt {
+        let bytes: &[u8; 32] = self
+            .borrow_front()
+            .try_into()
+            .expect("front stack item is not a felt");
+        Felt::from_bytes_be(bytes)
+    }
+
+    /// Push felts so that the first one ends up on top, the order in which
+    /// `pop_felts` returns them
+    fn push_felts(&mut self, felts: &[Felt]) {
+        for felt in felts.iter().rev() {
+            self.push_felt(*felt);
+        }
+    }
+
+    /// Pop `N` felts, the one on top first
+    fn pop_felts<const N: usize>(&mut self) -> [Felt; N] {
+        let mut felts = [Felt::ZERO; N];
+        for felt in felts.iter_mut() {
+            *felt = self.pop_felt();
+        }
+        felts
+    }
+
+    /// Push the projective coordinates of a point, `z` ends up on top
+    fn push_point(&mut self, point: &ShortWeierstrassProjectivePoint<StarkCurve>) {
+        self.push_felt(Felt(*point.x()));
+        self.push_felt(Felt(*point.y()));
+        self.push_felt(Felt(*point.z()));
+    }
+
+    /// Pop a point pushed with `push_point`
+    fn pop_point(&mut self) -> ShortWeierstrassProjectivePoint<StarkCurve> {
+        let [z, y, x] = self.pop_felts();
+        ShortWeierstrassProjectivePoint::new([x.0, y.0, z.0]).unwrap()
+    }
+}
+
+impl<T: BidirectionalStack> FeltStack for T {}
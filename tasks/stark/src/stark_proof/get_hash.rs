@@ -1,9 +1,15 @@
+use std::mem::offset_of;
+
 use crate::{
-    felt::Felt, pedersen::PedersenHash, poseidon::PoseidonHashMany,
-    swiftness::stark::types::StarkProof,
+    felt::Felt, pedersen::PedersenHash, poseidon::PoseidonHashMany, stack::FeltStack,
+    swiftness::stark::types::StarkProof, HASH_STEP_COMPUTE_UNITS,
+};
+use utils::{
+    impl_type_identifiable, is_valid_discriminant, BidirectionalStack, Executable, Frame,
+    TypeIdentifiable,
 };
-use utils::{impl_type_identifiable, BidirectionalStack, Executable, TypeIdentifiable};
 
+#[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GetHashStep {
     Init,
@@ -15,6 +21,7 @@ pub enum GetHashStep {
 }
 
 #[repr(C)]
+#[derive(Debug)]
 pub struct GetHash {
     step: GetHashStep,
     main_page_hash: Felt,
@@ -24,7 +31,7 @@ pub struct GetHash {
     accumulated_hash: Felt,
 }
 
-impl_type_identifiable!(GetHash);
+impl_type_identifiable!(GetHash, 0x3f0e_fcad);
 
 impl GetHash {
     pub fn new(n_verifier_friendly_commitment_layers: Felt) -> Self {
@@ -71,11 +78,7 @@ impl Executable for GetHash {
                 vec![PedersenHash::new().to_vec_with_type_tag()]
             }
             GetHashStep::WaitForPedersenAddress => {
-                let bytes = stack.borrow_front();
-                let pedersen_result = Felt::from_bytes_be_slice(bytes);
-                stack.pop_front();
-                stack.pop_front();
-                stack.pop_front();
+                let pedersen_result = PedersenHash::pop_result(stack);
 
                 self.accumulated_hash = pedersen_result;
 
@@ -92,11 +95,7 @@ impl Executable for GetHash {
                 vec![PedersenHash::new().to_vec_with_type_tag()]
             }
             GetHashStep::WaitForPedersenValue => {
-                let bytes = stack.borrow_front();
-                let pedersen_result = Felt::from_bytes_be_slice(bytes);
-                stack.pop_front();
-                stack.pop_front();
-                stack.pop_front();
+                let pedersen_result = PedersenHash::pop_result(stack);
 
                 self.accumulated_hash = pedersen_result;
                 self.current_memory_index += 1;
@@ -122,11 +121,7 @@ impl Executable for GetHash {
                 }
             }
             GetHashStep::MainPageHash => {
-                let bytes = stack.borrow_front();
-                self.main_page_hash = Felt::from_bytes_be_slice(bytes);
-                stack.pop_front();
-                stack.pop_front();
-                stack.pop_front();
+                self.main_page_hash = PedersenHash::pop_result(stack);
 
                 let (
                     n_verifier_friendly_commitment_layers,
@@ -174,13 +169,7 @@ impl Executable for GetHash {
                 total_elements += 5; // padding_addr, padding_value, headers_len+1, main_page_len, main_page_hash
                 total_elements += headers_len * 3;
 
-                let inputs_with_one = total_elements + 1;
-                let zero_count = inputs_with_one.div_ceil(2) * 2 - inputs_with_one;
-                for _ in 0..zero_count {
-                    stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
-                }
-
-                stack.push_front(&Felt::ONE.to_bytes_be()).unwrap();
+                PoseidonHashMany::push_padding(total_elements, stack);
 
                 for i in (0..headers_len).rev() {
                     let (start_address, size, hash) = {
@@ -190,22 +179,16 @@ impl Executable for GetHash {
                         (header.start_address, header.size, header.hash)
                     };
 
-                    stack.push_front(&hash.to_bytes_be()).unwrap();
-                    stack.push_front(&size.to_bytes_be()).unwrap();
-                    stack.push_front(&start_address.to_bytes_be()).unwrap();
+                    stack.push_felt(hash);
+                    stack.push_felt(size);
+                    stack.push_felt(start_address);
                 }
 
-                stack
-                    .push_front(&self.main_page_hash.to_bytes_be())
-                    .unwrap();
-                stack
-                    .push_front(&Felt::from(main_page_len).to_bytes_be())
-                    .unwrap();
-                stack
-                    .push_front(&Felt::from(headers_len + 1).to_bytes_be())
-                    .unwrap();
-                stack.push_front(&padding_value.to_bytes_be()).unwrap();
-                stack.push_front(&padding_addr.to_bytes_be()).unwrap();
+                stack.push_felt(self.main_page_hash);
+                stack.push_felt(Felt::from(main_page_len));
+                stack.push_felt(Felt::from(headers_len + 1));
+                stack.push_felt(padding_value);
+                stack.push_felt(padding_addr);
 
                 for i in (0..segments_len).rev() {
                     let (begin_addr, stop_ptr) = {
@@ -215,8 +198,8 @@ impl Executable for GetHash {
                         (segment.begin_addr, segment.stop_ptr)
                     };
 
-                    stack.push_front(&stop_ptr.to_bytes_be()).unwrap();
-                    stack.push_front(&begin_addr.to_bytes_be()).unwrap();
+                    stack.push_felt(stop_ptr);
+                    stack.push_felt(begin_addr);
                 }
 
                 if has_dynamic_params {
@@ -226,30 +209,29 @@ impl Executable for GetHash {
                         let dynamic_params_vec: Vec<u32> = (*dynamic_params).into();
                         for value in dynamic_params_vec.iter().rev() {
                             let felt = Felt::from(*value);
-                            stack.push_front(&felt.to_bytes_be()).unwrap();
+                            stack.push_felt(felt);
                         }
                     }
                 }
 
-                stack.push_front(&layout.to_bytes_be()).unwrap();
-                stack.push_front(&range_check_max.to_bytes_be()).unwrap();
-                stack.push_front(&range_check_min.to_bytes_be()).unwrap();
-                stack.push_front(&log_n_steps.to_bytes_be()).unwrap();
-                stack
-                    .push_front(&n_verifier_friendly_commitment_layers.to_bytes_be())
-                    .unwrap();
+                stack.push_felt(layout);
+                stack.push_felt(range_check_max);
+                stack.push_felt(range_check_min);
+                stack.push_felt(log_n_steps);
+                stack.push_felt(n_verifier_friendly_commitment_layers);
 
-                stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
-                stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
-                stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
+                PoseidonHashMany::push_initial_state(stack);
 
                 self.step = GetHashStep::Program;
-                vec![PoseidonHashMany::new(total_elements).to_vec_with_type_tag()]
+                vec![
+                    PoseidonHashMany::with_budget(total_elements, HASH_STEP_COMPUTE_UNITS)
+                        .to_vec_with_type_tag(),
+                ]
             }
             GetHashStep::Program => {
-                let bytes = stack.borrow_front();
-                let poseidon_result = Felt::from_bytes_be_slice(bytes);
-                println!("GetHash result: {:?}", poseidon_result);
+                // Only the hash is left as the result of the task
+                let poseidon_result = PoseidonHashMany::pop_result(stack);
+                stack.push_felt(poseidon_result);
 
                 self.step = GetHashStep::Done;
                 vec![]
@@ -263,6 +245,17 @@ impl Executable for GetHash {
     fn is_finished(&mut self) -> bool {
         self.step == GetHashStep::Done
     }
+
+    fn frame(&self) -> Option<Frame> {
+        Some(Frame {
+            inputs: 0,
+            outputs: 1,
+        })
+    }
+
+    fn is_valid_bytes(bytes: &[u8]) -> bool {
+        is_valid_discriminant(bytes, offset_of!(Self, step), GetHashStep::Done as u8)
+    }
 }
 
 impl GetHash {
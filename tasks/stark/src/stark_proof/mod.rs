@@ -1,8 +1,21 @@
-use utils::{impl_type_identifiable, BidirectionalStack, Executable, TypeIdentifiable};
+use std::mem::offset_of;
 
-use crate::{felt::Felt, poseidon::PoseidonHashMany, swiftness::stark::types::StarkProof};
+use utils::{
+    impl_type_identifiable, is_valid_discriminant, BidirectionalStack, Executable, Frame,
+    TypeIdentifiable,
+};
+
+use crate::{
+    felt::Felt,
+    poseidon::{PoseidonHashMainPage, PoseidonHashMany},
+    stack::FeltStack,
+    stark_proof::reject::RejectReason,
+    swiftness::stark::types::StarkProof,
+    HASH_STEP_COMPUTE_UNITS,
+};
 
 pub mod get_hash;
+pub mod reject;
 pub mod stark_commit;
 pub mod stark_verify;
 pub mod validate_public_input;
@@ -14,6 +27,7 @@ pub const MAX_RANGE_CHECK: Felt = Felt::from_hex_unchecked("0xffff");
 pub const MAX_ADDRESS: usize = 0xffffffffffffffff;
 pub const INITIAL_PC: usize = 1;
 
+#[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HashPublicInputsStep {
     Init,
@@ -34,22 +48,84 @@ pub mod segments {
 }
 
 #[repr(C)]
+#[derive(Debug)]
 pub struct HashPublicInputs {
     pub step: HashPublicInputsStep,
     pub program_input_length: usize,
     pub output_input_length: usize,
     pub program_hash: Felt,
+    pub source: PublicInputSource,
+    /// First `main_page` cell of the program and of the output, read when
+    /// the source is `PublicInputSource::MainPage`
+    pub program_start: usize,
+    pub output_start: usize,
+}
+
+/// Where `HashPublicInputs` reads the program and the output from
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublicInputSource {
+    /// The front stack, where they are pushed before the task
+    FrontStack,
+    /// The `main_page` of the proof
+    MainPage,
 }
 
-impl_type_identifiable!(HashPublicInputs);
+// The tags of the proof tasks are pinned since in-flight verifications keep
+// them in their stack accounts across program upgrades. Changing the layout
+// of a pinned task requires bumping `verifier::state::ACCOUNT_VERSION`.
+impl_type_identifiable!(HashPublicInputs, 0x336d_db16);
 
 impl HashPublicInputs {
+    /// Hash inputs pushed on the front stack, the program on top of the
+    /// output, each laid out as `PoseidonHashMany::push_input` does
     pub fn new(program_input_length: usize, output_input_length: usize) -> Self {
         Self {
             step: HashPublicInputsStep::Init,
             program_input_length,
             output_input_length,
             program_hash: Felt::ZERO,
+            source: PublicInputSource::FrontStack,
+            program_start: 0,
+            output_start: 0,
+        }
+    }
+
+    /// Hash the program and the output straight from the proof's `main_page`
+    pub fn from_main_page(
+        program_start: usize,
+        program_input_length: usize,
+        output_start: usize,
+        output_input_length: usize,
+    ) -> Self {
+        Self {
+            source: PublicInputSource::MainPage,
+            program_start,
+            output_start,
+            ..Self::new(program_input_length, output_input_length)
+        }
+    }
+
+    /// Task hashing the program or the output, whichever is next
+    fn hash_task(&self, program: bool) -> Vec<u8> {
+        let len = if program {
+            self.program_input_length
+        } else {
+            self.output_input_length
+        };
+        match self.source {
+            PublicInputSource::MainPage => {
+                let start = if program {
+                    self.program_start
+                } else {
+                    self.output_start
+                };
+                PoseidonHashMainPage::with_budget(start, len, HASH_STEP_COMPUTE_UNITS)
+                    .to_vec_with_type_tag()
+            }
+            PublicInputSource::FrontStack => {
+                PoseidonHashMany::with_budget(len, HASH_STEP_COMPUTE_UNITS).to_vec_with_type_tag()
+            }
         }
     }
 }
@@ -59,27 +135,19 @@ impl Executable for HashPublicInputs {
         match self.step {
             HashPublicInputsStep::Init => {
                 self.step = HashPublicInputsStep::ProgramHash;
-                vec![PoseidonHashMany::new(self.program_input_length).to_vec_with_type_tag()]
+                vec![self.hash_task(true)]
             }
             HashPublicInputsStep::ProgramHash => {
-                let bytes = stack.borrow_front();
-                let program_hash = Felt::from_bytes_be_slice(bytes);
-                stack.pop_front();
-                stack.pop_front();
-                stack.pop_front();
+                let program_hash = PoseidonHashMany::pop_result(stack);
                 self.program_hash = program_hash;
                 self.step = HashPublicInputsStep::OutputHash;
-                vec![PoseidonHashMany::new(self.output_input_length).to_vec_with_type_tag()]
+                vec![self.hash_task(false)]
             }
             HashPublicInputsStep::OutputHash => {
-                let bytes = stack.borrow_front();
-                let output_hash = Felt::from_bytes_be_slice(bytes);
-                stack.pop_front();
-                stack.pop_front();
-                stack.pop_front();
+                let output_hash = PoseidonHashMany::pop_result(stack);
 
-                stack.push_front(&output_hash.to_bytes_be()).unwrap();
-                stack.push_front(&self.program_hash.to_bytes_be()).unwrap();
+                // The result is the program hash on top of the output hash
+                stack.push_felts(&[self.program_hash, output_hash]);
 
                 self.step = HashPublicInputsStep::Done;
                 vec![]
@@ -93,37 +161,53 @@ impl Executable for HashPublicInputs {
     fn is_finished(&mut self) -> bool {
         self.step == HashPublicInputsStep::Done
     }
+
+    fn frame(&self) -> Option<Frame> {
+        if self.source == PublicInputSource::MainPage {
+            return Some(Frame {
+                inputs: 0,
+                outputs: 2,
+            });
+        }
+        // The inputs of both hashes, pushed before the task
+        let program = PoseidonHashMany::new(self.program_input_length).frame()?;
+        let output = PoseidonHashMany::new(self.output_input_length).frame()?;
+        Some(Frame {
+            inputs: program.inputs + output.inputs,
+            outputs: 2,
+        })
+    }
+
+    fn is_valid_bytes(bytes: &[u8]) -> bool {
+        is_valid_discriminant(
+            bytes,
+            offset_of!(Self, step),
+            HashPublicInputsStep::Done as u8,
+        ) && is_valid_discriminant(
+            bytes,
+            offset_of!(Self, source),
+            PublicInputSource::MainPage as u8,
+        )
+    }
 }
+#[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VerifyPublicInputStep {
     Init,
-    Output,
-    Program,
     Done,
 }
 #[repr(C)]
+#[derive(Debug)]
 pub struct VerifyPublicInput {
     step: VerifyPublicInputStep,
-    program_start: usize,
-    program_end: usize,
-    program_len: usize,
-    output_start: usize,
-    output_end: usize,
-    output_len: usize,
 }
 
-impl_type_identifiable!(VerifyPublicInput);
+impl_type_identifiable!(VerifyPublicInput, 0xb406_ce86);
 
 impl VerifyPublicInput {
     pub fn new() -> Self {
         Self {
             step: VerifyPublicInputStep::Init,
-            program_start: 0,
-            program_end: 0,
-            output_start: 0,
-            output_end: 0,
-            program_len: 0,
-            output_len: 0,
         }
     }
 }
@@ -141,107 +225,69 @@ impl Executable for VerifyPublicInput {
                 let proof: &StarkProof = stack.get_proof_reference();
                 let public_segments = &proof.public_input.segments;
 
-                let initial_pc: usize = public_segments
-                    .get(segments::PROGRAM)
-                    .unwrap()
-                    .begin_addr
-                    .try_into()
-                    .unwrap();
-                let initial_fp: usize = public_segments
-                    .get(segments::EXECUTION)
-                    .unwrap()
-                    .begin_addr
-                    .try_into()
-                    .unwrap();
-                let final_ap: usize = public_segments
-                    .get(segments::EXECUTION)
-                    .unwrap()
-                    .stop_ptr
-                    .try_into()
-                    .unwrap();
-
-                assert!(
-                    initial_fp < MAX_ADDRESS,
-                    "Initial AP exceeds maximum address"
-                );
-                assert!(final_ap < MAX_ADDRESS, "Final AP exceeds maximum address");
-                assert!(
-                    proof.public_input.continuous_page_headers.is_empty(),
-                    "Continuous page headers are not empty"
-                );
-                assert!(initial_pc == INITIAL_PC, "Wrong initial PC");
+                let address = |segment: usize, stop: bool| {
+                    let segment = public_segments.get(segment)?;
+                    let address = if stop {
+                        segment.stop_ptr
+                    } else {
+                        segment.begin_addr
+                    };
+                    usize::try_from(address).ok()
+                };
+                let (
+                    Some(initial_pc),
+                    Some(initial_fp),
+                    Some(final_ap),
+                    Some(output_start),
+                    Some(output_end),
+                ) = (
+                    address(segments::PROGRAM, false),
+                    address(segments::EXECUTION, false),
+                    address(segments::EXECUTION, true),
+                    address(segments::OUTPUT, false),
+                    address(segments::OUTPUT, true),
+                )
+                else {
+                    return RejectReason::InvalidSegmentAddress.reject();
+                };
 
-                //1. Program segment
-                let program_end_pc: usize = initial_fp - 2;
-                let program_len = program_end_pc - initial_pc;
-
-                let output_start: usize = public_segments
-                    .get(segments::OUTPUT)
-                    .unwrap()
-                    .begin_addr
-                    .try_into()
-                    .unwrap();
-                let output_end: usize = public_segments
-                    .get(segments::OUTPUT)
-                    .unwrap()
-                    .stop_ptr
-                    .try_into()
-                    .unwrap();
-                let output_len = output_end - output_start;
-                let output_start = proof.public_input.main_page.0.len() - output_len;
-
-                self.output_start = output_start;
-                self.output_end = proof.public_input.main_page.0.len();
-                self.output_len = output_len;
-
-                self.program_end = program_len;
-                self.program_len = program_len;
-
-                self.step = VerifyPublicInputStep::Output;
-                vec![]
-            }
-            VerifyPublicInputStep::Output => {
-                let inputs_len = self.output_len + 1;
-                let zero_count = inputs_len.div_ceil(2) * 2 - inputs_len;
-                for _ in 0..zero_count {
-                    stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
+                if initial_fp == MAX_ADDRESS {
+                    return RejectReason::InitialFpExceedsMaximum.reject();
                 }
-                stack.push_front(&Felt::ONE.to_bytes_be()).unwrap();
-
-                for i in (self.output_start..self.output_end).rev() {
-                    let proof: &StarkProof = stack.get_proof_reference();
-                    let memory = proof.public_input.main_page.0.as_slice();
-                    let item = memory[i].value;
-                    stack.push_front(&item.to_bytes_be()).unwrap();
+                if final_ap == MAX_ADDRESS {
+                    return RejectReason::FinalApExceedsMaximum.reject();
                 }
-
-                stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
-                stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
-                stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
-                self.step = VerifyPublicInputStep::Program;
-                vec![]
-            }
-            VerifyPublicInputStep::Program => {
-                let inputs_len = self.program_len + 1;
-                let zero_count = inputs_len.div_ceil(2) * 2 - inputs_len;
-                for _ in 0..zero_count {
-                    stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
+                if !proof.public_input.continuous_page_headers.is_empty() {
+                    return RejectReason::ContinuousPageHeadersNotEmpty.reject();
+                }
+                if initial_pc != INITIAL_PC {
+                    return RejectReason::WrongInitialPc.reject();
+                }
+                if initial_fp < initial_pc + 2 {
+                    return RejectReason::InvalidSegmentAddress.reject();
+                }
+                let main_page_len = proof.public_input.main_page.0.len();
+                if output_end < output_start || output_end - output_start > main_page_len {
+                    return RejectReason::InvalidOutputSegment.reject();
                 }
 
-                stack.push_front(&Felt::ONE.to_bytes_be()).unwrap();
-                for i in (self.program_start..self.program_end).rev() {
-                    let proof: &StarkProof = stack.get_proof_reference();
-                    let memory = proof.public_input.main_page.0.as_slice();
-                    let item = memory[i].value;
-                    stack.push_front(&item.to_bytes_be()).unwrap();
+                //1. Program segment
+                let program_end_pc: usize = initial_fp - 2;
+                let program_len = program_end_pc - initial_pc;
+                if program_len > main_page_len {
+                    return RejectReason::InvalidProgramSegment.reject();
                 }
-                stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
-                stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
-                stack.push_front(&Felt::ZERO.to_bytes_be()).unwrap();
 
-                self.step = VerifyPublicInputStep::Done;
+                // 2. Output segment, at the end of the main page
+                let output_len = output_end - output_start;
+                let output_start = main_page_len - output_len;
 
-                vec![HashPublicInputs::new(self.program_len, self.output_len).to_vec_with_type_tag()]
+                // Both are hashed straight from the main page
+                self.step = VerifyPublicInputStep::Done;
+                vec![
+                    HashPublicInputs::from_main_page(0, program_len, output_start, output_len)
+                        .to_vec_with_type_tag(),
+                ]
             }
             VerifyPublicInputStep::Done => {
                 vec![]
@@ -252,4 +298,12 @@ impl Executable for VerifyPublicInput {
     fn is_finished(&mut self) -> bool {
         self.step == VerifyPublicInputStep::Done
     }
+
+    fn is_valid_bytes(bytes: &[u8]) -> bool {
+        is_valid_discriminant(
+            bytes,
+            offset_of!(Self, step),
+            VerifyPublicInputStep::Done as u8,
+        )
+    }
 }
@@ -0,0 +1,60 @@
+use utils::{Executable, Reject};
+
+/// Reasons for rejecting a proof, recorded as the error code of a rejected
+/// verification account. Codes are stable and must not be reused.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    LogNStepsExceedsMaximum = 1,
+    InvalidTraceLength = 2,
+    InvalidSegmentsCount = 3,
+    InvalidRangeCheckMin = 4,
+    RangeCheckMinNotBelowMax = 5,
+    RangeCheckMaxExceedsMaximum = 6,
+    InvalidLayoutCode = 7,
+    OutputUsesExceedMaximum = 8,
+    PedersenUsesExceedCopies = 9,
+    RangeCheckUsesExceedCopies = 10,
+    BitwiseUsesExceedCopies = 11,
+    PoseidonUsesExceedCopies = 12,
+    InvalidSegmentAddress = 13,
+    InitialFpExceedsMaximum = 14,
+    FinalApExceedsMaximum = 15,
+    ContinuousPageHeadersNotEmpty = 16,
+    WrongInitialPc = 17,
+    InvalidOutputSegment = 18,
+    InvalidProgramSegment = 19,
+}
+
+impl RejectReason {
+    /// Tasks to return from `Executable::execute` to reject the proof
+    pub fn reject(self) -> Vec<Vec<u8>> {
+        vec![Reject::new(self as u32).to_vec_with_type_tag()]
+    }
+
+    pub fn from_code(code: u32) -> Option<Self> {
+        use RejectReason::*;
+        Some(match code {
+            1 => LogNStepsExceedsMaximum,
+            2 => InvalidTraceLength,
+            3 => InvalidSegmentsCount,
+            4 => InvalidRangeCheckMin,
+            5 => RangeCheckMinNotBelowMax,
+            6 => RangeCheckMaxExceedsMaximum,
+            7 => InvalidLayoutCode,
+            8 => OutputUsesExceedMaximum,
+            9 => PedersenUsesExceedCopies,
+            10 => RangeCheckUsesExceedCopies,
+            11 => BitwiseUsesExceedCopies,
+            12 => PoseidonUsesExceedCopies,
+            13 => InvalidSegmentAddress,
+            14 => InitialFpExceedsMaximum,
+            15 => FinalApExceedsMaximum,
+            16 => ContinuousPageHeadersNotEmpty,
+            17 => WrongInitialPc,
+            18 => InvalidOutputSegment,
+            19 => InvalidProgramSegment,
+            _ => return None,
+        })
+    }
+}
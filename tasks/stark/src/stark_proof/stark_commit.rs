@@ -1,5 +1,11 @@
-use utils::{impl_type_identifiable, BidirectionalStack, Executable, TypeIdentifiable};
+use std::mem::offset_of;
 
+use utils::{
+    impl_type_identifiable, is_valid_discriminant, BidirectionalStack, Executable, Frame,
+    TypeIdentifiable,
+};
+
+#[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StarkCommitStep {
     Init,
@@ -8,11 +14,12 @@ pub enum StarkCommitStep {
     Done,
 }
 #[repr(C)]
+#[derive(Debug)]
 pub struct StarkCommit {
     step: StarkCommitStep,
 }
 
-impl_type_identifiable!(StarkCommit);
+impl_type_identifiable!(StarkCommit, 0xb46f_8e8b);
 
 impl StarkCommit {
     pub fn new() -> Self {
@@ -52,4 +59,15 @@ impl Executable for StarkCommit {
     fn is_finished(&mut self) -> bool {
         self.step == StarkCommitStep::Done
     }
+
+    fn frame(&self) -> Option<Frame> {
+        Some(Frame {
+            inputs: 0,
+            outputs: 0,
+        })
+    }
+
+    fn is_valid_bytes(bytes: &[u8]) -> bool {
+        is_valid_discriminant(bytes, offset_of!(Self, step), StarkCommitStep::Done as u8)
+    }
 }
@@ -1,9 +1,16 @@
+use std::mem::offset_of;
+
 use crate::felt::NonZeroFelt;
+use crate::stark_proof::reject::RejectReason;
 use crate::stark_proof::segments;
 use crate::stark_proof::{MAX_LOG_N_STEPS, MAX_RANGE_CHECK};
 use crate::{felt::Felt, swiftness::stark::types::StarkProof};
-use utils::{impl_type_identifiable, BidirectionalStack, Executable, TypeIdentifiable};
+use utils::{
+    impl_type_identifiable, is_valid_discriminant, BidirectionalStack, Executable, Frame,
+    TypeIdentifiable,
+};
 
+#[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ValidatePublicInputStep {
     Validate,
@@ -11,11 +18,12 @@ pub enum ValidatePublicInputStep {
 }
 
 #[repr(C)]
+#[derive(Debug)]
 pub struct ValidatePublicInput {
     step: ValidatePublicInputStep,
 }
 
-impl_type_identifiable!(ValidatePublicInput);
+impl_type_identifiable!(ValidatePublicInput, 0x2a0a_b74c);
 
 impl ValidatePublicInput {
     pub fn new() -> Self {
@@ -42,51 +50,46 @@ impl Executable for ValidatePublicInput {
                 let trace_domain_size = Felt::TWO.pow_felt(&log_trace_domain_size);
 
                 // 1. Validate log_n_steps
-                assert!(
-                    public_input.log_n_steps < MAX_LOG_N_STEPS,
-                    "log_n_steps exceeds maximum"
-                );
+                if public_input.log_n_steps >= MAX_LOG_N_STEPS {
+                    return RejectReason::LogNStepsExceedsMaximum.reject();
+                }
 
                 // 2. Validate trace length
                 let n_steps = FELT_2.pow_felt(&public_input.log_n_steps);
                 let expected_trace_length =
                     n_steps * Felt::from(CPU_COMPONENT_HEIGHT) * Felt::from(CPU_COMPONENT_STEP);
 
-                assert!(
-                    expected_trace_length == trace_domain_size,
-                    "Trace length is invalid"
-                );
+                if expected_trace_length != trace_domain_size {
+                    return RejectReason::InvalidTraceLength.reject();
+                }
 
                 // 3. Validate segments count
-                assert!(
-                    public_input.segments.len() == segments::N_SEGMENTS,
-                    "Invalid number of segments"
-                );
+                if public_input.segments.len() != segments::N_SEGMENTS {
+                    return RejectReason::InvalidSegmentsCount.reject();
+                }
 
                 // 4. Validate range check bounds
-                assert!(
-                    FELT_0 <= public_input.range_check_min,
-                    "Range check min is invalid"
-                );
-                assert!(
-                    public_input.range_check_min < public_input.range_check_max,
-                    "Range check min must be less than max"
-                );
-                assert!(
-                    public_input.range_check_max <= MAX_RANGE_CHECK,
-                    "Range check max exceeds maximum"
-                );
+                if public_input.range_check_min < FELT_0 {
+                    return RejectReason::InvalidRangeCheckMin.reject();
+                }
+                if public_input.range_check_min >= public_input.range_check_max {
+                    return RejectReason::RangeCheckMinNotBelowMax.reject();
+                }
+                if public_input.range_check_max > MAX_RANGE_CHECK {
+                    return RejectReason::RangeCheckMaxExceedsMaximum.reject();
+                }
 
                 // 5. Validate layout
-                assert!(public_input.layout == LAYOUT_CODE, "Invalid layout code");
+                if public_input.layout != LAYOUT_CODE {
+                    return RejectReason::InvalidLayoutCode.reject();
+                }
 
                 // 6. Validate output uses
                 let output_segment = &public_input.segments.as_slice()[segments::OUTPUT];
                 let output_uses = output_segment.stop_ptr - output_segment.begin_addr;
-                assert!(
-                    output_uses <= u128::MAX.into(),
-                    "Output uses exceed maximum"
-                );
+                if output_uses > u128::MAX.into() {
+                    return RejectReason::OutputUsesExceedMaximum.reject();
+                }
 
                 // 7. Validate pedersen uses
                 let pedersen_copies = trace_domain_size.field_div(
@@ -96,10 +99,9 @@ impl Executable for ValidatePublicInput {
                 let pedersen_segment = &public_input.segments.as_slice()[segments::PEDERSEN];
                 let pedersen_uses = (pedersen_segment.stop_ptr - pedersen_segment.begin_addr)
                     .field_div(&NonZeroFelt::from_felt_unchecked(FELT_3));
-                assert!(
-                    pedersen_uses <= pedersen_copies,
-                    "Pedersen uses exceed copies"
-                );
+                if pedersen_uses > pedersen_copies {
+                    return RejectReason::PedersenUsesExceedCopies.reject();
+                }
 
                 // 8. Validate range check uses
                 let range_check_copies = trace_domain_size.field_div(
@@ -109,10 +111,9 @@ impl Executable for ValidatePublicInput {
                 let range_check_segment = &public_input.segments.as_slice()[segments::RANGE_CHECK];
                 let range_check_uses =
                     range_check_segment.stop_ptr - range_check_segment.begin_addr;
-                assert!(
-                    range_check_uses <= range_check_copies,
-                    "Range check uses exceed copies"
-                );
+                if range_check_uses > range_check_copies {
+                    return RejectReason::RangeCheckUsesExceedCopies.reject();
+                }
 
                 // 9. Validate bitwise uses
                 let bitwise_copies = trace_domain_size.field_div(
@@ -122,7 +123,9 @@ impl Executable for ValidatePublicInput {
                 let bitwise_segment = &public_input.segments.as_slice()[segments::BITWISE];
                 let bitwise_uses = (bitwise_segment.stop_ptr - bitwise_segment.begin_addr)
                     .field_div(&NonZeroFelt::from_felt_unchecked(FELT_5));
-                assert!(bitwise_uses <= bitwise_copies, "Bitwise uses exceed copies");
+                if bitwise_uses > bitwise_copies {
+                    return RejectReason::BitwiseUsesExceedCopies.reject();
+                }
 
                 // 10. Validate poseidon uses
                 let poseidon_copies = trace_domain_size.field_div(
@@ -132,10 +135,9 @@ impl Executable for ValidatePublicInput {
                 let poseidon_segment = &public_input.segments.as_slice()[segments::POSEIDON];
                 let poseidon_uses = (poseidon_segment.stop_ptr - poseidon_segment.begin_addr)
                     .field_div(&NonZeroFelt::from_felt_unchecked(FELT_6));
-                assert!(
-                    poseidon_uses <= poseidon_copies,
-                    "Poseidon uses exceed copies"
-                );
+                if poseidon_uses > poseidon_copies {
+                    return RejectReason::PoseidonUsesExceedCopies.reject();
+                }
 
                 self.step = ValidatePublicInputStep::Done;
                 vec![]
@@ -150,6 +152,21 @@ impl Executable for ValidatePublicInput {
     fn is_finished(&mut self) -> bool {
         self.step == ValidatePublicInputStep::Done
     }
+
+    fn frame(&self) -> Option<Frame> {
+        Some(Frame {
+            inputs: 0,
+            outputs: 0,
+        })
+    }
+
+    fn is_valid_bytes(bytes: &[u8]) -> bool {
+        is_valid_discriminant(
+            bytes,
+            offset_of!(Self, step),
+            ValidatePublicInputStep::Done as u8,
+        )
+    }
 }
 
 // Constants
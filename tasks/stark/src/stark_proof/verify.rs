@@ -1,4 +1,8 @@
-use utils::{impl_type_identifiable, BidirectionalStack, Executable, TypeIdentifiable};
+use std::mem::offset_of;
+
+use utils::{
+    impl_type_identifiable, is_valid_discriminant, BidirectionalStack, Executable, TypeIdentifiable,
+};
 
 use crate::stark_proof::stark_commit::StarkCommit;
 use crate::stark_proof::stark_verify::StarkVerify;
@@ -6,6 +10,7 @@ use crate::stark_proof::validate_public_input::ValidatePublicInput;
 use crate::stark_proof::VerifyPublicInput;
 use crate::{felt::Felt, stark_proof::get_hash::GetHash};
 
+#[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VerifyStep {
     ValidatePublicInput,
@@ -17,11 +22,12 @@ pub enum VerifyStep {
 }
 
 #[repr(C)]
+#[derive(Debug)]
 pub struct Verify {
     step: VerifyStep,
 }
 
-impl_type_identifiable!(Verify);
+impl_type_identifiable!(Verify, 0x4f69_2a2a);
 
 impl Verify {
     pub fn new() -> Self {
@@ -69,4 +75,8 @@ impl Executable for Verify {
     fn is_finished(&mut self) -> bool {
         self.step == VerifyStep::Done
     }
+
+    fn is_valid_bytes(bytes: &[u8]) -> bool {
+        is_valid_discriminant(bytes, offset_of!(Self, step), VerifyStep::Done as u8)
+    }
 }
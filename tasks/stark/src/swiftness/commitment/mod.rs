@@ -1,2 +1,21 @@
 pub mod table;
 pub mod vector;
+
+use crate::{
+    felt::Felt,
+    keccak::{keccak_felts, masked_160, FeltEncoding},
+    poseidon::hades::HadesPermutation,
+};
+
+/// Hash of two sibling nodes of a commitment, Poseidon in the
+/// verifier-friendly layers and Keccak masked to 160 bits in the others
+///
+/// `verifier_friendly` tells whether the layer of the nodes is one of the
+/// `n_verifier_friendly_commitment_layers` of the commitment `Config`.
+pub fn hash_node(x: Felt, y: Felt, verifier_friendly: bool) -> Felt {
+    if verifier_friendly {
+        HadesPermutation::permute([x, y, Felt::TWO])[0]
+    } else {
+        masked_160(&keccak_felts(&[x, y], FeltEncoding::BigEndian))
+    }
+}
@@ -97,8 +97,8 @@ mod test {
             witness: StarkWitness::default(),
         };
         println!("proof: {proof:?}");
-        let mut proof_clone = proof.clone();
-        let bytes = cast_struct_to_slice(&mut proof_clone);
+        let proof_clone = proof.clone();
+        let bytes = cast_struct_to_slice(&proof_clone);
 
         let proof_from_bytes = cast_slice_to_struct::<StarkProof>(bytes);
         assert_eq!(proof_from_bytes, &proof);